@@ -0,0 +1,172 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::{load_line, EnvError, EnvLoader, Marker};
+
+
+/// An ENV file which can be changed and written back without losing anything else in it.
+/// 
+/// Every line is kept exactly as it was read, with its comments, whitespace and line break. Changing a
+/// value only replaces the value itself, so quotes around it and a comment after it stay where they are.
+/// The file is read with the comment style, delimiter and quote options of the EnvLoader it is parsed with.
+/// Keys are matched the way they are written in the file, before any key option like a prefix is applied.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// // main.rs
+/// use env_plus::{Document, EnvLoader};
+/// 
+/// fn main() {
+///     let loader = EnvLoader::new();
+///     let source = std::fs::read_to_string("./.env_plus").unwrap();
+/// 
+///     let mut document = Document::parse(&source, &loader);
+///     document.set("SECRET", "NEW_SECRET");
+/// 
+///     std::fs::write("./.env_plus", document.to_string()).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Document {
+    lines: Vec<Line>,
+    delimiter: String,
+    comment: Marker,
+}
+
+
+#[derive(Clone, Debug)]
+struct Line {
+    text: String,
+    ending: String,
+    /// The key on the line and where its value is in the text, for lines with an entry.
+    entry: Option<(String, Range<usize>)>,
+}
+
+
+impl Document {
+    /// Splits the source into lines and finds the entry on each of them. Lines which can't be parsed are
+    /// kept as they are, like comments.
+    pub fn parse(source: &str, loader: &EnvLoader) -> Document {
+        let mut lines = Vec::new();
+
+        for (ind, raw) in source.split_inclusive('\n').enumerate() {
+            let text = raw.trim_end_matches(['\r', '\n']);
+
+            let entry = match load_line(text, loader, ind) {
+                Ok(Some(entry)) => {
+                    let start = entry.value.as_ptr() as usize - text.as_ptr() as usize;
+                    let quoted = text[..start].ends_with(['"', '\'']) && text[start + entry.value.len()..].starts_with(['"', '\'']);
+
+                    // The whitespace around an unquoted value is formatting, so it is kept when the value changes.
+                    let value = if quoted { entry.value.as_ref() } else { entry.value.trim() };
+                    let start = value.as_ptr() as usize - text.as_ptr() as usize;
+
+                    Some((entry.key.trim().to_string(), start..start + value.len()))
+                },
+                _ => None,
+            };
+
+            lines.push(Line { text: text.to_string(), ending: raw[text.len()..].to_string(), entry });
+        }
+
+        Document { lines, delimiter: loader.value_delimiter.to_text(), comment: loader.comment.clone() }
+    }
+
+    /// Returns the value of the first line with the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match &line.entry {
+            Some((found, range)) if found == key => Some(&line.text[range.clone()]),
+            _ => None,
+        })
+    }
+
+    /// Replaces the value of every line with the key. When the key is not in the document, a new line with
+    /// it is added at the end. The value is written as it is given, so a value with the comment style in it
+    /// has to be quoted by the caller.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let mut found = false;
+
+        for line in &mut self.lines {
+            if let Some((line_key, range)) = &mut line.entry {
+                if line_key != key { continue };
+
+                line.text.replace_range(range.clone(), value);
+                *range = range.start..range.start + value.len();
+                found = true;
+            }
+        }
+
+        if found { return };
+
+        let ending = self.lines.iter().map(|line| line.ending.as_str()).find(|ending| !ending.is_empty()).unwrap_or("\n").to_string();
+
+        if let Some(last) = self.lines.last_mut() {
+            if last.ending.is_empty() { last.ending = ending.clone() };
+        }
+
+        let text = format!("{}{}{}", key, self.delimiter, value);
+        let start = text.len() - value.len();
+
+        self.lines.push(Line { text, ending, entry: Some((key.to_string(), start..start + value.len())) });
+    }
+
+    /// Returns the document without its comments, together with the comments in the sidecar format. Lines
+    /// which are only a comment are left out, and a comment after a value is removed with the whitespace
+    /// before it. Everything else stays as it was.
+    /// 
+    /// Each line of the sidecar holds one comment: the number of the line it was on in the document, a tab,
+    /// and the comment from its comment style to the end of the line, like `3\t// The port to listen on`.
+    pub fn split_comments(&self) -> (String, String) {
+        let mut text = String::new();
+        let mut sidecar = String::new();
+
+        for (ind, line) in self.lines.iter().enumerate() {
+            let comment = match &line.entry {
+                Some((_, range)) => self.comment.find(&line.text[range.end..]).map(|pos| range.end + pos),
+                None => {
+                    let trimmed = line.text.trim_start();
+                    if !self.comment.is_empty() && self.comment.starts(trimmed) { Some(line.text.len() - trimmed.len()) } else { None }
+                },
+            };
+
+            match comment {
+                Some(start) => {
+                    sidecar.push_str(&format!("{}\t{}\n", ind + 1, &line.text[start..]));
+
+                    if line.entry.is_some() {
+                        text.push_str(line.text[..start].trim_end());
+                        text.push_str(&line.ending);
+                    }
+                },
+                None => {
+                    text.push_str(&line.text);
+                    text.push_str(&line.ending);
+                },
+            }
+        }
+
+        (text, sidecar)
+    }
+
+    /// Writes the document without its comments to the path, and the comments to a sidecar file next to it
+    /// with `.comments` added to its name, see fn split_comments for the format.
+    pub fn write_without_comments(&self, path: &str) -> Result<(), EnvError> {
+        let (text, sidecar) = self.split_comments();
+        let sidecar_path = format!("{}.comments", path);
+
+        std::fs::write(path, text).map_err(|source| EnvError::Io { path: path.to_string(), source })?;
+        std::fs::write(&sidecar_path, sidecar).map_err(|source| EnvError::Io { path: sidecar_path.clone(), source })
+    }
+}
+
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}{}", line.text, line.ending)?;
+        }
+
+        Ok(())
+    }
+}
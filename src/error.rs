@@ -0,0 +1,190 @@
+use std::fmt;
+
+
+/// The error returned when an ENV file could not be loaded.
+#[derive(Debug)]
+pub enum EnvError {
+    /// The file could not be read.
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// A line could not be split into a key and a value with the current delimiter.
+    Malformed {
+        line: usize,
+        content: String,
+    },
+    /// A key and its alias are both in the file with different values.
+    AliasConflict {
+        alias: String,
+        canonical: String,
+    },
+    /// A TypedSink could not accept the value of a key.
+    Sink {
+        line: usize,
+        key: String,
+        message: String,
+    },
+    /// A required key is neither in the file nor in the environment.
+    MissingRequired {
+        key: String,
+        /// A key from the file which looks like a typo of the missing one.
+        suggestion: Option<String>,
+    },
+    /// A quoted value is missing its closing quote.
+    UnterminatedQuote {
+        line: usize,
+        content: String,
+    },
+    /// A result could not be turned into JSON or another format.
+    Serialize {
+        message: String,
+    },
+    /// The file is not valid UTF-8 or starts with a byte order mark while fn strict_utf8 is on.
+    Encoding {
+        path: String,
+        message: String,
+    },
+    /// An interpolated value grew past the limit set with fn max_expanded_value_length.
+    ExpansionTooLong {
+        line: usize,
+        key: String,
+        limit: usize,
+    },
+    /// A value doesn't match the FieldSpec given to fn validate_schema.
+    InvalidValue {
+        line: usize,
+        key: String,
+        message: String,
+    },
+    /// The file can be read by its group or by other users while fn require_secure_permissions is on.
+    InsecurePermissions {
+        path: String,
+        mode: u32,
+    },
+    /// A key is both a value and the parent of other keys in fn parse_nested.
+    NestingConflict {
+        key: String,
+        other: String,
+    },
+    /// A value references a field in another file which could not be read, see fn resolve_file_references.
+    Reference {
+        line: usize,
+        key: String,
+        message: String,
+    },
+    /// Setting the variables would make the environment larger than the limit, see fn check_env_size.
+    EnvTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// A `${NAME}` could not be resolved while fn strict_interpolation is on.
+    UnresolvedReference {
+        line: usize,
+        key: String,
+        name: String,
+    },
+    /// The archive from fn change_archive_entry could not be read.
+    Archive {
+        path: String,
+        message: String,
+    },
+    /// The archive from fn change_archive_entry doesn't have the entry.
+    MissingArchiveEntry {
+        archive: String,
+        entry: String,
+    },
+    /// No profile with the name was registered, see fn EnvLoader::profile.
+    UnknownProfile {
+        name: String,
+    },
+    /// A key is longer than the limit set with fn max_key_length.
+    KeyTooLong {
+        line: usize,
+        length: usize,
+        limit: usize,
+    },
+    /// Two files give a key different values while fn conflict_policy is ConflictPolicy::Error. The values of
+    /// keys passed to fn mark_secret are `***`.
+    Conflict {
+        key: String,
+        first_file: String,
+        first_value: String,
+        second_file: String,
+        second_value: String,
+    },
+    /// There is no file to read, like after fn load_embedded_dir with an empty directory.
+    NoFiles,
+}
+
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Io { path, source } => write!(f, "Could not read '{}': {}", path, source),
+            EnvError::Malformed { line, content } => {
+                write!(f, "Line {} with content '{}' does not appear to be formatted properly.", line, content)
+            }
+            EnvError::AliasConflict { alias, canonical } => {
+                write!(f, "'{}' and its alias '{}' are both set, but with different values.", canonical, alias)
+            }
+            EnvError::Sink { line, key, message } => {
+                write!(f, "Line {} with key '{}' could not be set: {}", line, key, message)
+            }
+            EnvError::MissingRequired { key, suggestion: Some(suggestion) } => {
+                write!(f, "Required key '{}' is missing, did you mean '{}'?", key, suggestion)
+            }
+            EnvError::MissingRequired { key, suggestion: None } => write!(f, "Required key '{}' is missing.", key),
+            EnvError::UnterminatedQuote { line, content } => {
+                write!(f, "Line {} with content '{}' opens a quote which is never closed.", line, content)
+            }
+            EnvError::Serialize { message } => write!(f, "Could not serialize the result: {}", message),
+            EnvError::Encoding { path, message } => write!(f, "'{}' is not strict UTF-8: {}", path, message),
+            EnvError::ExpansionTooLong { line, key, limit } => {
+                write!(f, "Line {} with key '{}' expands to more than {} bytes.", line, key, limit)
+            }
+            EnvError::InvalidValue { line, key, message } => {
+                write!(f, "Line {} with key '{}' has an invalid value: {}", line, key, message)
+            }
+            EnvError::InsecurePermissions { path, mode } => {
+                write!(f, "'{}' has mode {:o}, but must not be readable by its group or other users.", path, mode & 0o777)
+            }
+            EnvError::NestingConflict { key, other } => {
+                write!(f, "'{}' and '{}' can't be nested together, as one would be both a value and a parent.", key, other)
+            }
+            EnvError::Reference { line, key, message } => {
+                write!(f, "Line {} with key '{}' references a value which could not be loaded: {}", line, key, message)
+            }
+            EnvError::EnvTooLarge { size, limit } => {
+                write!(f, "The environment would grow to {} bytes, more than the limit of {} bytes.", size, limit)
+            }
+            EnvError::UnresolvedReference { line, key, name } => {
+                write!(f, "Line {} with key '{}' references '{}', which is not set.", line, key, name)
+            }
+            EnvError::Archive { path, message } => write!(f, "Could not read the archive '{}': {}", path, message),
+            EnvError::MissingArchiveEntry { archive, entry } => {
+                write!(f, "The archive '{}' has no entry named '{}'.", archive, entry)
+            }
+            EnvError::UnknownProfile { name } => write!(f, "There is no profile named '{}'.", name),
+            EnvError::KeyTooLong { line, length, limit } => {
+                write!(f, "Line {} has a key of {} bytes, more than the limit of {} bytes.", line, length, limit)
+            }
+            EnvError::Conflict { key, first_file, first_value, second_file, second_value } => write!(
+                f,
+                "'{}' is '{}' in '{}', but '{}' in '{}'.",
+                key, first_value, first_file, second_value, second_file,
+            ),
+            EnvError::NoFiles => write!(f, "There are no files to load."),
+        }
+    }
+}
+
+
+impl std::error::Error for EnvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EnvError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -12,18 +12,18 @@
 //! env_plus = "0.1.2"
 //! ```
 //! ### .env_plus
-//! ```
+//! ```text
 //! // This is a comment!
 //! SECRET=YOUR_SECRET
 //! ```
 
 //! ### main<nolink>.rs
-//! ```rust
+//! ```no_run
 //! use env_plus::EnvLoader;
 //! 
 //! fn main() {
 //!     EnvLoader::new()
-//!     .activate();
+//!     .activate().unwrap();
 //!
 //!     let secret = std::env::var("SECRET").unwrap();
 //!     assert_eq!(secret, String::from("YOUR_SECRET"));
@@ -35,9 +35,456 @@
 //! crate.<br />
 
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+mod document;
+mod error;
+mod pipeline;
+mod profile;
+#[cfg(feature = "figment")]
+mod provider;
+#[cfg(test)]
 mod tests;
 
+pub use document::Document;
+pub use error::EnvError;
+pub use pipeline::Pipeline;
+pub use profile::{Profile, ProfileRegistry};
+
+
+/// The case keys are changed to with fn uppercase_keys and fn lowercase_keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyCase {
+    Upper,
+    Lower,
+}
+
+
+/// How keys are case folded when fn uppercase_keys or fn lowercase_keys is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseFolding {
+    /// Only the ASCII letters a-z and A-Z are changed, everything else is kept as it is.
+    /// The result is the same on every platform and for every language.
+    Ascii,
+    /// The full Unicode case mapping is used, so letters such as 'ı' or 'ß' are changed too.
+    Unicode,
+}
+
+
+/// Which value wins in fn merged_with_env when a key is both in the file and in the environment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precedence {
+    /// The value from the file is used.
+    FilePreferred,
+    /// The value already in the environment is used.
+    OsPreferred,
+}
+
+
+/// The file contents together with the entries parsed from them, returned by fn parse_with_source.
+#[derive(Clone, Debug)]
+pub struct ParsedSource {
+    /// The contents of the file as they were read.
+    pub source: String,
+    /// Every entry in the file in order, repeated keys included.
+    pub entries: Vec<SourceEntry>,
+    overwrite: bool,
+}
+
+
+impl ParsedSource {
+    /// Returns the source text of every key exactly as it was written, found through SourceEntry::span. An
+    /// entry which spans more than one line includes all of them with the line breaks between them. When a
+    /// key is in the file more than once, the line whose value is loaded is used: the first one, or the last
+    /// one when overwriting is on.
+    pub fn raw_lines(&self) -> HashMap<&str, &str> {
+        let mut lines = HashMap::new();
+
+        for entry in &self.entries {
+            let raw = &self.source[entry.span.clone()];
+
+            if self.overwrite {
+                lines.insert(entry.key.as_str(), raw);
+            } else {
+                lines.entry(entry.key.as_str()).or_insert(raw);
+            }
+        }
+
+        lines
+    }
+}
+
+
+/// The type a value has to parse as, see FieldSpec.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Any value.
+    #[default]
+    String,
+    /// A value which parses as an `i64`.
+    Integer,
+    /// A value which parses as an `f64`.
+    Float,
+    /// Either `true` or `false`.
+    Bool,
+}
+
+
+/// The rules for a single key, checked by fn validate_schema.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: String,
+    /// Whether the key has to be in the file or in the environment.
+    pub required: bool,
+    pub kind: FieldKind,
+    /// The only values the key may have. Any value is allowed when this is empty.
+    pub allowed_values: Vec<String>,
+}
+
+
+/// How a bracketed list is recognized and loaded, see fn parse_bracketed_lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListFormat {
+    /// The character which opens a list, `[` by default.
+    pub open: char,
+    /// The character which closes a list, `]` by default.
+    pub close: char,
+    /// The character between the items, `,` by default.
+    pub separator: char,
+    /// If true, every item is set as its own variable with the position appended to the key, so
+    /// `HOSTS=[a, b]` sets `HOSTS_0` and `HOSTS_1`. Otherwise the items are joined with the separator and set
+    /// as one value, `a,b`.
+    pub indexed: bool,
+    /// If true, a value which opens a list without closing it fails with an EnvError::Malformed. Otherwise
+    /// it is loaded as it is written.
+    pub strict: bool,
+}
+
+
+impl Default for ListFormat {
+    fn default() -> Self {
+        ListFormat { open: '[', close: ']', separator: ',', indexed: false, strict: true }
+    }
+}
+
+
+/// A formatting problem found by fn check_formatting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatIssue {
+    /// The line ends with spaces or tabs.
+    TrailingWhitespace,
+    /// The indentation of the line has both spaces and tabs.
+    MixedIndentation,
+    /// The file doesn't end with a line break.
+    MissingFinalNewline,
+}
+
+
+/// A line of a file which fn check_formatting found a problem on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatWarning {
+    pub file: String,
+    /// The line the problem is on, starting from 1.
+    pub line: usize,
+    pub issue: FormatIssue,
+}
+
+
+/// The contents of every file, read by fn read_buffers and kept so they can be parsed without copying.
+/// 
+/// The keys and values from fn FileBuffers::parse borrow from the buffers, so they can't outlive them.
+/// A key or value which is loaded exactly as it is written in the file is a `Cow::Borrowed` slice of the
+/// buffer, even when quotes or whitespace were removed around it. One that an option had to change, like
+/// a case change, a prefix or interpolation, is a `Cow::Owned` string. Call `into_owned` on the ones which have to be
+/// kept after the buffers are dropped.
+#[derive(Clone)]
+pub struct FileBuffers {
+    files: Vec<String>,
+    loader: EnvLoader,
+}
+
+
+/// A key and value from fn FileBuffers::parse, borrowed from the buffers where possible.
+pub type BorrowedPair<'a> = (Cow<'a, str>, Cow<'a, str>);
+
+
+impl FileBuffers {
+    /// Parses the buffers the same way fn EnvLoader::parse_ordered parses the files. The buffers can be
+    /// parsed again as often as needed, without reading the files another time.
+    pub fn parse(&self) -> Result<Vec<BorrowedPair<'_>>, EnvError> {
+        let mut parsed = Vec::new();
+
+        for (ind, file) in self.files.iter().enumerate() {
+            parsed.extend(parse_source(file, ind, &self.loader)?);
+        }
+
+        let entries = process_entries(&self.loader, parsed, &mut Vec::new())?;
+
+        Ok(entries.into_iter().map(|entry| (entry.key, entry.value)).collect())
+    }
+}
+
+
+#[cfg(feature = "zeroize")]
+impl Drop for FileBuffers {
+    fn drop(&mut self) {
+        for file in &mut self.files {
+            zeroize::Zeroize::zeroize(file);
+        }
+    }
+}
+
+
+/// A single key and value from fn parse_with_source, with the place it was found at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceEntry {
+    pub key: String,
+    pub value: String,
+    /// The line (or record) the entry is on, starting from 1.
+    pub line: usize,
+    /// The byte range of the whole line in ParsedSource::source, without the line break. An entry which is
+    /// continued on the next lines, see fn line_continuation and fn greedy_values, covers all of them.
+    pub span: Range<usize>,
+}
+
+
+/// A key and value from fn parse_documented, with the comment written above it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentedEntry {
+    pub key: String,
+    pub value: String,
+    /// The line the entry is on, starting from 1.
+    pub line: usize,
+    /// The comment lines directly above the entry without the comment style, one item per line.
+    pub doc: Vec<String>,
+}
+
+
+/// A block of comment lines which is not attached to any entry, see fn parse_documented.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentBlock {
+    /// The line the block starts on, starting from 1.
+    pub line: usize,
+    /// The comment lines without the comment style, one item per line.
+    pub lines: Vec<String>,
+}
+
+
+/// The result of fn parse_documented.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Documentation {
+    /// Every entry with the comment above it, in the order of the files.
+    pub entries: Vec<DocumentedEntry>,
+    /// The comment blocks which are separated from the next entry by an empty line, or come after the last one.
+    pub comments: Vec<CommentBlock>,
+}
+
+
+/// What happened during the last fn activate, returned by fn report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The files which were loaded, in order.
+    pub files: Vec<PathBuf>,
+    /// How many distinct keys were parsed from the files.
+    pub parsed: usize,
+    /// The keys which were set in the environment, in the order they were set.
+    pub set: Vec<String>,
+    /// The keys which were left alone because they were already in the environment.
+    pub skipped: Vec<String>,
+    /// Problems which didn't stop the load.
+    pub warnings: Vec<String>,
+    /// The error which stopped the load, if there was one.
+    pub errors: Vec<String>,
+    /// The keys given to fn apply_only which were found in the files.
+    pub requested_found: Vec<String>,
+    /// The keys given to fn apply_only which were not in any of the files.
+    pub requested_missing: Vec<String>,
+    /// How long reading and parsing the files took, when fn measure_load_time is on.
+    pub elapsed: Option<Duration>,
+    /// Whether the files were skipped because they didn't change since the last load, see fn skip_unchanged.
+    pub cache_hit: bool,
+    /// The keys whose value in the files was already the value in the environment, see fn redundant_keys.
+    pub redundant: Vec<String>,
+    /// The keys which were removed from the environment, see fn unset_value_token.
+    pub unset: Vec<String>,
+}
+
+
+/// Receives the parsed keys and values in fn activate_into_typed and turns them into typed values.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use env_plus::TypedSink;
+/// 
+/// #[derive(Default)]
+/// struct Config {
+///     port: u16,
+///     debug: bool,
+/// }
+/// 
+/// impl TypedSink for Config {
+///     fn set_typed(&mut self, key: &str, value: &str) -> Result<(), String> {
+///         match key {
+///             "PORT" => self.port = value.parse().map_err(|err| format!("{}", err))?,
+///             "DEBUG" => self.debug = value == "true",
+///             _ => {},
+///         }
+/// 
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait TypedSink {
+    /// Called once for every key in the file. Returning an error stops the parsing.
+    fn set_typed(&mut self, key: &str, value: &str) -> Result<(), String>;
+}
+
+
+/// What happens to a value which opens a quote but never closes it, see fn unterminated_quote_policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnterminatedQuote {
+    /// Loading fails with an EnvError::UnterminatedQuote.
+    Error,
+    /// The opening quote is kept as a normal character, so `KEY="oops` loads `"oops`.
+    Literal,
+    /// The value is everything after the opening quote up to the end of the line, comments included,
+    /// so `KEY="oops // note` loads `oops // note`.
+    ToEndOfLine,
+}
+
+
+/// Decides which file wins when the same key is in more than one of them, see fn overwrite_policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// The file added last wins.
+    FileOrder,
+    /// The file which was modified most recently wins. Files with the same modification time, or whose
+    /// modification time can't be read, fall back to the order they were added in.
+    NewerFileWins,
+}
+
+
+/// What to do when two files give the same key different values, see fn conflict_policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The value from the file which wins by the OverwritePolicy is used without a word.
+    Ignore,
+    /// The winning value is used, and a warning with both files and values is added to the report.
+    Warn,
+    /// Loading fails with an EnvError::Conflict.
+    Error,
+}
+
+
+/// What to do with a line which could not be parsed, returned from the callback given to fn on_error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recovery {
+    /// The line is ignored and parsing continues with the next one.
+    Skip,
+    /// Parsing stops and the error is returned.
+    Abort,
+    /// The line is parsed again with this content instead. If the new content fails as well, its error
+    /// is returned without calling the callback again.
+    Replace(String),
+}
+
+
+/// What fn activate does when setting the variables would make the environment larger than the limit from
+/// fn env_size_limit, see fn check_env_size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeLimitAction {
+    /// A warning is added to the report and the variables are set anyway.
+    Warn,
+    /// Loading fails with an EnvError::EnvTooLarge and nothing is set.
+    Error,
+}
+
+
+/// What an operator registered with fn assignment_operators does with its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assignment {
+    /// The value is set like with the normal delimiter.
+    Set,
+    /// The value is only used when the key is neither earlier in the files nor in the environment, even if
+    /// overwriting is on.
+    SetIfUnset,
+    /// The value is added to the end of the earlier value of the key in the files, or of the one in the
+    /// environment if there is none, joined with the separator from fn append_separator.
+    Append,
+}
+
+
+/// Which references fn interpolate treats as an error instead of replacing them with an empty string, see
+/// fn strict_interpolation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrictInterpolation {
+    /// Every reference is replaced, with an empty string if the name is not found.
+    Off,
+    /// A name which is neither an earlier key nor in the environment is an error.
+    Undefined,
+    /// A name which is not found, or whose value is empty, is an error.
+    UndefinedOrEmpty,
+}
+
+
+/// What fn activate_with_progress is doing, passed to its callback.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The file is about to be read.
+    FileStarted(PathBuf),
+    /// The file was read, with the number of entries parsed from it.
+    FileFinished(PathBuf, usize),
+    /// The key was set in the environment.
+    KeySet(String),
+}
+
+
+/// Where a key given to fn require gets its value from, returned by fn require_status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequireSource {
+    /// The key is in one of the files.
+    File,
+    /// The key is not in the files, but is already in the environment.
+    Environment,
+    /// The key is in neither, so loading would fail.
+    Missing,
+}
+
+
+type ErrorHandler = Arc<Mutex<dyn FnMut(usize, &str, &EnvError) -> Recovery + Send>>;
+
+type FileResolver = Arc<Mutex<dyn FnMut() -> Result<String, EnvError> + Send>>;
+
+
+/// A difference between two files, returned from fn diff_files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnvChange {
+    /// The key is only in the new file.
+    Added {
+        key: String,
+        value: String,
+    },
+    /// The key is only in the old file.
+    Removed {
+        key: String,
+        value: String,
+    },
+    /// The key is in both files with different values.
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
 
 /// The entry point of the library
 /// 
@@ -48,10 +495,86 @@ mod tests;
 /// * Overwrite - bool (default false) which specifies if the already exisiting ENVs will be replaced or not if you have a var with the same name. Change with fn overwrite_envs
 #[derive(Clone)]
 pub struct EnvLoader {
-    file: String,
-    comment: String,
-    value_delimiter: String,
-    overwrite: bool
+    files: Vec<String>,
+    comment: Marker,
+    value_delimiter: Marker,
+    overwrite: bool,
+    collapse_delimiters: bool,
+    key_token: Option<String>,
+    key_case: Option<KeyCase>,
+    case_folding: CaseFolding,
+    record_separator: Option<String>,
+    aliases: Vec<(String, String)>,
+    limit: Option<usize>,
+    required: Vec<String>,
+    typo_distance: usize,
+    expand_paths: bool,
+    #[cfg(feature = "zeroize")]
+    secrets: Vec<String>,
+    strip_prefix: Option<String>,
+    add_prefix: Option<String>,
+    quotes: bool,
+    unterminated_quote: UnterminatedQuote,
+    sources: HashMap<String, PathBuf>,
+    shell_compat: bool,
+    overwrite_policy: OverwritePolicy,
+    trim: bool,
+    report: LoadReport,
+    separator_chars: Vec<char>,
+    apply_only: Option<Vec<String>>,
+    on_error: Option<ErrorHandler>,
+    add_suffix: Option<String>,
+    greedy_values: bool,
+    strict_utf8: bool,
+    simple_interpolation: bool,
+    interpolate: bool,
+    max_expanded_length: Option<usize>,
+    key_separators: Vec<char>,
+    measure_time: bool,
+    lists: bool,
+    list_format: ListFormat,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    secure_permissions: bool,
+    file_resolver: Option<FileResolver>,
+    skip_unchanged: bool,
+    content_hash: Option<u64>,
+    append_keys: Vec<String>,
+    append_separator: String,
+    #[cfg(feature = "serde")]
+    file_references: bool,
+    env_size_action: Option<SizeLimitAction>,
+    env_size_limit: usize,
+    operators: Vec<(Marker, Assignment)>,
+    path_lists: Vec<String>,
+    delimiters: Vec<Marker>,
+    strict_interpolation: StrictInterpolation,
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    archive_entry: Option<(String, String)>,
+    overrides: Vec<(String, String)>,
+    env_reference_marker: Option<String>,
+    cr_newlines: bool,
+    strip_invisible: bool,
+    protected: Vec<String>,
+    max_key_length: Option<usize>,
+    continuation: bool,
+    continuation_join: String,
+    skip_first_line: bool,
+    match_key_case: bool,
+    #[cfg(feature = "include_dir")]
+    embedded: Option<&'static include_dir::Dir<'static>>,
+    trailing_separator: Option<char>,
+    digit_grouping: Option<char>,
+    unset_token: Option<String>,
+    conflict_policy: ConflictPolicy,
+    keep_empty_items: bool,
+    context: Option<HashMap<String, String>>,
+}
+
+
+impl Default for EnvLoader {
+    fn default() -> Self {
+        EnvLoader::new()
+    }
 }
 
 
@@ -61,20 +584,20 @@ impl EnvLoader {
     /// 
     /// # Examples
     /// 
-    /// ```
+    /// ```text
     /// // .env_plus
     /// 
     /// // Double slash is used for commenting, equal sign is used for assiging a value by default.
     /// SECRET=YOUR_SECRET
     /// ```
     /// 
-    /// ``` 
+    /// ```no_run
     /// // main.rs
     /// use env_plus::EnvLoader;
     /// 
     /// fn main() {
     ///     EnvLoader::new()
-    ///     .activate();
+    ///     .activate().unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     ///     assert_eq!(secret, String::from("YOUR_SECRET"));
@@ -83,10 +606,78 @@ impl EnvLoader {
     /// 
     pub fn new() -> EnvLoader {
         EnvLoader {
-            file: String::from("./.env_plus"),
-            comment: String::from("//"),
-            value_delimiter: String::from("="),
+            files: vec![String::from("./.env_plus")],
+            comment: Marker::Text(String::from("//")),
+            value_delimiter: Marker::Char('='),
             overwrite: false,
+            collapse_delimiters: false,
+            key_token: None,
+            key_case: None,
+            case_folding: CaseFolding::Ascii,
+            record_separator: None,
+            aliases: Vec::new(),
+            limit: None,
+            required: Vec::new(),
+            typo_distance: 2,
+            expand_paths: false,
+            #[cfg(feature = "zeroize")]
+            secrets: Vec::new(),
+            strip_prefix: None,
+            add_prefix: None,
+            quotes: false,
+            unterminated_quote: UnterminatedQuote::Error,
+            sources: HashMap::new(),
+            shell_compat: false,
+            overwrite_policy: OverwritePolicy::FileOrder,
+            trim: false,
+            report: LoadReport::default(),
+            separator_chars: Vec::new(),
+            apply_only: None,
+            on_error: None,
+            add_suffix: None,
+            greedy_values: false,
+            strict_utf8: false,
+            simple_interpolation: false,
+            interpolate: false,
+            max_expanded_length: None,
+            key_separators: Vec::new(),
+            measure_time: false,
+            lists: false,
+            list_format: ListFormat::default(),
+            secure_permissions: false,
+            file_resolver: None,
+            skip_unchanged: false,
+            content_hash: None,
+            append_keys: Vec::new(),
+            append_separator: String::from(","),
+            #[cfg(feature = "serde")]
+            file_references: false,
+            env_size_action: None,
+            env_size_limit: DEFAULT_ENV_SIZE_LIMIT,
+            operators: Vec::new(),
+            path_lists: Vec::new(),
+            delimiters: Vec::new(),
+            strict_interpolation: StrictInterpolation::Off,
+            #[cfg(any(feature = "tar", feature = "zip"))]
+            archive_entry: None,
+            overrides: Vec::new(),
+            env_reference_marker: None,
+            cr_newlines: false,
+            strip_invisible: false,
+            protected: Vec::new(),
+            max_key_length: None,
+            continuation: false,
+            continuation_join: String::from("\n"),
+            skip_first_line: false,
+            match_key_case: false,
+            #[cfg(feature = "include_dir")]
+            embedded: None,
+            trailing_separator: None,
+            digit_grouping: None,
+            unset_token: None,
+            conflict_policy: ConflictPolicy::Ignore,
+            keep_empty_items: false,
+            context: None,
         }
     }
 
@@ -95,20 +686,20 @@ impl EnvLoader {
     /// 
     /// # Examples
     /// 
-    /// ```
+    /// ```text
     /// // my_special_file.extension
     /// 
     /// SECRET=YOUR_SECRET
     /// ```
     /// 
-    /// ```
+    /// ```no_run
     /// // main.rs
     /// use env_plus::EnvLoader;
     /// 
     /// fn main() {
     ///     EnvLoader::new()
     ///     .change_file(String::from("./my_special_file.extension"))
-    ///     .activate();
+    ///     .activate().unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     /// 
@@ -117,7 +708,112 @@ impl EnvLoader {
     /// }
     /// ```
     pub fn change_file(mut self, path: String) -> Self {
-        self.file = path;
+        self.files = vec![path];
+
+        self
+    }
+
+    /// Adds another file to load after the ones already set. Files are parsed in the order they were added,
+    /// and when the same key is in more than one of them the value from the last file wins, which lets a
+    /// local file override a base one. fn overwrite_envs only decides what happens to variables which were
+    /// already in the environment before loading.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET=BASE_SECRET
+    /// 
+    /// // .env_plus.local
+    /// 
+    /// SECRET=YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .add_file(String::from("./.env_plus.local"))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn add_file(mut self, path: String) -> Self {
+        self.files.push(path);
+
+        self
+    }
+
+    /// Loads a single entry of a `.zip` or `.tar` archive instead of a file, which needs the `zip` or the `tar`
+    /// feature. Only that entry is extracted, in memory. A missing archive returns an EnvError::Io like a
+    /// missing file, and an archive without the entry returns an EnvError::MissingArchiveEntry.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .change_archive_entry(String::from("./bundle.zip"), String::from("config/.env_plus"))
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    pub fn change_archive_entry(mut self, archive_path: String, entry_name: String) -> Self {
+        self.files = vec![archive_path.clone()];
+        self.archive_entry = Some((archive_path, entry_name));
+
+        self
+    }
+
+    /// Loads every file of a directory compiled into the binary with `include_dir!`, instead of files from the
+    /// filesystem. Needs the `include_dir` feature. The files, including the ones in subdirectories, are loaded
+    /// in the order of their paths, so a later file replaces the values of an earlier one like with fn add_file.
+    /// Their paths are relative to the directory, like `local/override.env`, and are what fn report and
+    /// fn sources show. Nothing is read from the filesystem.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// use include_dir::{include_dir, Dir};
+    /// 
+    /// static DEFAULTS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/embedded");
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .load_embedded_dir(&DEFAULTS)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "include_dir")]
+    pub fn load_embedded_dir(mut self, dir: &'static include_dir::Dir<'static>) -> Self {
+        fn collect(dir: &include_dir::Dir<'_>, files: &mut Vec<String>) {
+            for entry in dir.entries() {
+                match entry {
+                    include_dir::DirEntry::Dir(dir) => collect(dir, files),
+                    include_dir::DirEntry::File(file) => files.push(file.path().to_string_lossy().into_owned()),
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(dir, &mut files);
+        files.sort();
+
+        self.files = files;
+        self.embedded = Some(dir);
 
         self
     }
@@ -127,28 +823,35 @@ impl EnvLoader {
     /// 
     /// # Examples
     /// 
-    /// ```
+    /// ```text
     /// // .env_plus 
     /// 
     /// --This is a now a comment
     /// SECRET=YOUR_SECRET
     /// ```
     /// 
-    /// ```
+    /// ```no_run
     /// // main.rs
     /// use env_plus::EnvLoader;
     /// 
     /// fn main() {
     ///     EnvLoader::new()
     ///     .change_comment(String::from("--"))
-    ///     .activate();
+    ///     .activate().unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     ///     assert_eq!(secret, String::from("YOUR_SECRET"));
     /// }
     /// ```
     pub fn change_comment(mut self, comment: String) -> Self {
-        self.comment = comment;
+        self.comment = Marker::Text(comment);
+
+        self
+    }
+
+    /// The same as fn change_comment for a comment style of a single character, like `#`.
+    pub fn change_comment_char(mut self, comment: char) -> Self {
+        self.comment = Marker::Char(comment);
 
         self
     }
@@ -156,29 +859,73 @@ impl EnvLoader {
     /// Change the delimiter that will be used to parse the file lines.
     /// The default delimiter is =
     /// 
+    /// Only the first delimiter on a line splits it, and everything after it is the value, so values which
+    /// contain the delimiter themselves, like `URL=https:/host?a=b&c=d`, don't have to be quoted.
+    /// 
     /// # Examples
     /// 
-    /// ```
+    /// ```text
     /// // .env_plus
     /// 
     /// SECRET===YOUR_SECRET
     /// ```
     /// 
-    /// ```
+    /// ```no_run
     /// // main.rs 
     /// use env_plus::EnvLoader;
     /// 
     /// fn main() {
     ///     EnvLoader::new()
     ///     .change_delimiter(String::from("==="))
-    ///     .activate();
+    ///     .activate().unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     ///     assert_eq!(secret, String::from("YOUR_SECRET"));
     /// }
     /// ```
     pub fn change_delimiter(mut self, delimiter: String) ->  Self {
-        self.value_delimiter = delimiter;
+        self.value_delimiter = Marker::Text(delimiter);
+
+        self
+    }
+
+    /// The same as fn change_delimiter for a delimiter of a single character, like `:`.
+    pub fn change_delimiter_char(mut self, delimiter: char) -> Self {
+        self.value_delimiter = Marker::Char(delimiter);
+
+        self
+    }
+
+    /// If true is passed, repeated delimiters right after the key are treated as one, so
+    /// with the default delimiter both `KEY==value` and `KEY===value` load `value`.
+    /// 
+    /// This works on whatever delimiter is set with fn change_delimiter and repeats of the whole
+    /// delimiter are collapsed, not single characters. With a delimiter of `==` the line
+    /// `KEY====value` loads `value`, while `KEY===value` loads `=value`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET===YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .collapse_delimiters(true)
+    ///     .activate().unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn collapse_delimiters(mut self, collapse: bool) -> Self {
+        self.collapse_delimiters = collapse;
 
         self
     }
@@ -188,13 +935,13 @@ impl EnvLoader {
     /// 
     /// # Examples
     /// 
-    /// ```
+    /// ```text
     /// // .env_plus
     /// 
     /// SECRET=YOUR_SECRET
     /// ```
     /// 
-    /// ```
+    /// ```no_run
     /// // main.rs
     /// use env_plus::EnvLoader;
     /// 
@@ -203,7 +950,7 @@ impl EnvLoader {
     /// 
     ///     EnvLoader::new()
     ///     .overwrite_envs(true)
-    ///     .activate();
+    ///     .activate().unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     ///     assert_eq!(secret, String::from("YOUR_SECRET"));
@@ -216,83 +963,4680 @@ impl EnvLoader {
     }
 
 
-    /// Activate the module and load your ENV file.
+    /// Removes the given token from the start of every key, together with any whitespace after it.
+    /// This is useful for files written for a shell, where each line starts with `export `.
+    /// Keys which don't start with the token are loaded as they are.
     /// 
-    /// # Examples 
+    /// Comments are always stripped before the token is looked at, so a comment can't end up in a key
+    /// and a line which has nothing left after the token (such as `export // KEY=value`) returns an error.
     /// 
-    /// ```
-    /// // special.env 
+    /// # Examples
     /// 
-    /// @ I really love my comment design.
-    /// SECRET||YOUR_SECRET
-    /// ```
+    /// ```text
+    /// // .env_plus
     /// 
+    /// export SECRET=YOUR_SECRET
     /// ```
+    /// 
+    /// ```no_run
     /// // main.rs 
     /// use env_plus::EnvLoader;
     /// 
     /// fn main() {
     ///     EnvLoader::new()
-    ///     .change_delimiter(String::from("||"))
-    ///     .change_comment(String::from("@"))
-    ///     .change_file(String::from("./special.env"))
-    ///     .activate();
+    ///     .strip_key_token(String::from("export "))
+    ///     .activate()
+    ///     .unwrap();
     /// 
     ///     let secret = std::env::var("SECRET").unwrap();
     ///     assert_eq!(secret, String::from("YOUR_SECRET"));
     /// }
     /// ```
-    pub fn activate(self) {
-        let files_loaded = load_file(self);
-
-        if !files_loaded {
-            eprintln!("An error has occured")
-        }
-    }
-}
-
-
-fn load_file(envs: EnvLoader) -> bool {
-    let file = fs::read_to_string(envs.file); // Add a correct error handling
-
-    if file.is_err() {
-        println!("{:?}", file.err());
-        false
-
-    } else {
-        let unwraped_file = file.unwrap();
-        let file_lines = unwraped_file.lines();
-
-        for (ind, line) in file_lines.enumerate() {
-            load_line(line, &envs.comment, &envs.value_delimiter, &envs.overwrite, ind);
-        };
+    pub fn strip_key_token(mut self, token: String) -> Self {
+        self.key_token = Some(token);
 
-        true
+        self
     }
 
-}
-
-
-fn load_line(line: &str, comment: &String, delimiter: &String, overwrite: &bool, ind: usize) {
-    if line.trim().starts_with(comment) || line.trim() == "" { return };
-
-    let split_line: Vec<&str> = line.split(comment).collect();
-    let main_line = split_line[0];
-
-    let key_value: Vec<&str> = main_line.splitn(2, delimiter).collect();
-    if key_value.len() < 2 { panic!("Line {} with content '{}' does not appear to be formatted properly.", ind + 1, line) };
-
-    let key = key_value[0];
-    let value = key_value[1];
+    /// If true is passed, every key is changed to uppercase before it's set.
+    /// Only ASCII letters are changed unless fn case_folding is used, see CaseFolding.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// secret=YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .uppercase_keys(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn uppercase_keys(mut self, uppercase: bool) -> Self {
+        self.key_case = if uppercase { Some(KeyCase::Upper) } else { None };
+
+        self
+    }
+
+    /// If true is passed, every key is changed to lowercase before it's set.
+    /// Only ASCII letters are changed unless fn case_folding is used, see CaseFolding.
+    /// 
+    /// Calling this replaces anything set with fn uppercase_keys and the other way around.
+    pub fn lowercase_keys(mut self, lowercase: bool) -> Self {
+        self.key_case = if lowercase { Some(KeyCase::Lower) } else { None };
+
+        self
+    }
+
+    /// Sets how keys are case folded by fn uppercase_keys and fn lowercase_keys. The default is
+    /// CaseFolding::Ascii, which doesn't depend on the language of the text. With CaseFolding::Unicode
+    /// the Turkish dotless 'ı' becomes 'I' and 'İ' becomes "i\u{307}", which may not be the name you expect.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{CaseFolding, EnvLoader};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .uppercase_keys(true)
+    ///     .case_folding(CaseFolding::Unicode)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn case_folding(mut self, folding: CaseFolding) -> Self {
+        self.case_folding = folding;
+
+        self
+    }
+
+    /// Changes what separates the records in the file. By default every line is a record, but some files
+    /// put all of their records on a single line, separated by something like `;` or a NUL character.
+    /// Comments and the delimiter work the same way in each record, and line breaks around a record are
+    /// removed. Errors point at the record number instead of the line number.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // single_line.env
+    /// 
+    /// SECRET=YOUR_SECRET;TOKEN=YOUR_TOKEN
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .change_file(String::from("./single_line.env"))
+    ///     .record_separator(String::from(";"))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let token = std::env::var("TOKEN").unwrap();
+    ///     assert_eq!(token, String::from("YOUR_TOKEN"));
+    /// }
+    /// ```
+    pub fn record_separator(mut self, separator: String) -> Self {
+        self.record_separator = if separator.is_empty() { None } else { Some(separator) };
+
+        self
+    }
+
+    /// Makes two keys stand for the same variable. If the file has either one of them, both are set to
+    /// its value, which eases moving from one name to another without changing every file.
+    /// An error is returned if the file has both keys with different values.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// DB_URL=postgres://localhost
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .alias(String::from("DB_URL"), String::from("DATABASE_URL"))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let url = std::env::var("DATABASE_URL").unwrap();
+    ///     assert_eq!(url, String::from("postgres://localhost"));
+    /// }
+    /// ```
+    pub fn alias(mut self, alias: String, canonical: String) -> Self {
+        self.aliases.push((alias, canonical));
+
+        self
+    }
+
+    /// Stops reading the file once the given number of entries has been parsed, which is handy
+    /// for previewing a large file. Comments and empty lines don't count towards the limit, and
+    /// anything after the last entry is ignored, even if it's not formatted properly.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let preview = EnvLoader::new()
+    ///     .limit(5)
+    ///     .parse_ordered()
+    ///     .unwrap();
+    /// 
+    ///     assert!(preview.len() <= 5);
+    /// }
+    /// ```
+    pub fn limit(mut self, entries: usize) -> Self {
+        self.limit = Some(entries);
+
+        self
+    }
+
+    /// Makes the given keys required. Loading returns an EnvError::MissingRequired if one of them is
+    /// neither in the file nor already in the environment. When a key in the file looks like a typo of
+    /// the missing one, the error suggests it (see fn typo_distance).
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// DATABSE_URL=postgres://localhost
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let err = EnvLoader::new()
+    ///     .require(vec![String::from("DATABASE_URL")])
+    ///     .activate()
+    ///     .unwrap_err();
+    /// 
+    ///     // Required key 'DATABASE_URL' is missing, did you mean 'DATABSE_URL'?
+    ///     println!("{}", err);
+    /// }
+    /// ```
+    pub fn require(mut self, keys: Vec<String>) -> Self {
+        self.required.extend(keys);
+
+        self
+    }
+
+    /// Sets how many single character edits a key in the file can be away from a missing required key
+    /// for it to be suggested as a typo. The default is 2, and 0 turns the suggestions off.
+    pub fn typo_distance(mut self, distance: usize) -> Self {
+        self.typo_distance = distance;
+
+        self
+    }
+
+    /// If true is passed, values which look like filesystem paths have a leading `~` replaced with the home
+    /// directory and `$NAME` or `${NAME}` replaced with the value of that environment variable. References
+    /// to variables which are not set are left as they are.
+    /// 
+    /// A value looks like a path when it has no whitespace, doesn't contain `://` (so URLs are left alone)
+    /// and either is `~`, starts with `~/` or contains a `/`. Everything else is loaded unchanged, so
+    /// values like `costs $5` are not touched.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// CACHE_DIR=~/.cache/app
+    /// LOG_DIR=$XDG_STATE_HOME/app
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .expand_paths_in_values(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let cache = std::env::var("CACHE_DIR").unwrap();
+    ///     assert!(!cache.starts_with('~'));
+    /// }
+    /// ```
+    pub fn expand_paths_in_values(mut self, expand: bool) -> Self {
+        self.expand_paths = expand;
+
+        self
+    }
+
+    /// Marks the given keys as secret, so the crate's own copies of their values are zeroized once they
+    /// have been set. Only available with the `zeroize` feature.
+    /// 
+    /// This only shortens how long the values sit in memory owned by env_plus. The process environment keeps
+    /// its own copy, which is not removed, and copies made by the standard library or the allocator when a
+    /// string grows can't be reached. The buffer the file was read into is zeroized as well if any key is
+    /// marked as secret. Only the final values are zeroized. The copies made on the way there are dropped
+    /// without it: the earlier value and the joined one of a key which is appended to, see fn assignment_operators
+    /// and fn append_across_reloads, the values in the lookup maps of fn interpolate and fn simple_interpolation,
+    /// and a value changed by fn strip_invisible_chars or fn expand_paths_in_values before it was changed.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .mark_secret(vec![String::from("SECRET")])
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "zeroize")]
+    pub fn mark_secret(mut self, keys: Vec<String>) -> Self {
+        self.secrets.extend(keys);
+
+        self
+    }
+
+    /// Removes the given prefix from every key which starts with it. Keys without the prefix are kept as they are.
+    /// 
+    /// The prefix is removed before the case of the key is changed and before fn add_prefix adds its own
+    /// prefix, so both together rename keys from one prefix to another in a single pass.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// OLD_SECRET=YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .strip_prefix(String::from("OLD_"))
+    ///     .add_prefix(String::from("NEW_"))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let secret = std::env::var("NEW_SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn strip_prefix(mut self, prefix: String) -> Self {
+        self.strip_prefix = Some(prefix);
+
+        self
+    }
+
+    /// Adds the given prefix to every key. The prefix is added last, after fn strip_prefix and the case
+    /// options, and is used exactly as given.
+    pub fn add_prefix(mut self, prefix: String) -> Self {
+        self.add_prefix = Some(prefix);
+
+        self
+    }
+
+    /// Replaces each of the given characters in keys with an underscore, so with `.` and `-` the keys
+    /// `DB.HOST` and `DB-HOST` are both set as `DB_HOST`. This happens before any of the other key options
+    /// and before repeated keys are dropped, so keys which end up the same are treated as the same key. When
+    /// that happens a warning naming both spellings is added to the report of fn activate.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .normalize_key_separators(vec!['.', '-'])
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let host = std::env::var("DB_HOST").unwrap();
+    /// }
+    /// ```
+    pub fn normalize_key_separators(mut self, separators: Vec<char>) -> Self {
+        self.key_separators = separators;
+
+        self
+    }
+
+    /// Adds the given suffix to every key, so with `_INSTANCE2` the key `PORT` is set as `PORT_INSTANCE2`.
+    /// Like fn add_prefix, it is added after the other key options and is used exactly as given.
+    pub fn add_suffix(mut self, suffix: String) -> Self {
+        self.add_suffix = Some(suffix);
+
+        self
+    }
+
+    /// If true is passed, values wrapped in double or single quotes are loaded without the quotes. Anything
+    /// inside the quotes is part of the value, even the comment style, so `KEY="a // b"` loads `a // b`.
+    /// Whitespace before the opening quote is ignored, and after the closing quote only whitespace or a comment
+    /// may follow. There are no escape sequences and quotes can't span more than one line.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// GREETING="Hello // World" // The greeting shown on start
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .parse_quotes(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let greeting = std::env::var("GREETING").unwrap();
+    ///     assert_eq!(greeting, String::from("Hello // World"));
+    /// }
+    /// ```
+    pub fn parse_quotes(mut self, quotes: bool) -> Self {
+        self.quotes = quotes;
+
+        self
+    }
+
+    /// Sets what happens when fn parse_quotes is on and a value opens a quote without closing it.
+    /// The default is UnterminatedQuote::Error.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, UnterminatedQuote};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .parse_quotes(true)
+    ///     .unterminated_quote_policy(UnterminatedQuote::Literal)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn unterminated_quote_policy(mut self, policy: UnterminatedQuote) -> Self {
+        self.unterminated_quote = policy;
+
+        self
+    }
+
+    /// If true is passed, lines written for a shell are accepted next to the normal ones. A leading `export `
+    /// is removed and the key and value may be separated by whitespace instead of the delimiter.
+    /// 
+    /// Each line is read like this:
+    /// * `export` followed by whitespace at the start of the line is removed
+    /// * If the key is followed by the delimiter, with or without whitespace in between, it's a `KEY=value` line
+    /// * Otherwise the first run of whitespace separates the key from the value
+    /// 
+    /// So `KEY = value` and `KEY=a b` load `value` and `a b`, while `KEY value=x` loads `value=x`.
+    /// Whitespace at the start of the value is removed in both forms.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// export SECRET=YOUR_SECRET
+    /// TOKEN YOUR_TOKEN
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .shell_compat(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let token = std::env::var("TOKEN").unwrap();
+    ///     assert_eq!(token, String::from("YOUR_TOKEN"));
+    /// }
+    /// ```
+    pub fn shell_compat(mut self, compat: bool) -> Self {
+        self.shell_compat = compat;
+
+        self
+    }
+
+    /// Sets which file wins when more than one of them has the same key. The default is
+    /// OverwritePolicy::FileOrder, where the file added last wins.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, OverwritePolicy};
+    /// 
+    /// fn main() {
+    ///     // Whichever of the two files was deployed last is used.
+    ///     EnvLoader::new()
+    ///     .change_file(String::from("./a.env"))
+    ///     .add_file(String::from("./b.env"))
+    ///     .overwrite_policy(OverwritePolicy::NewerFileWins)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn overwrite_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite_policy = policy;
+
+        self
+    }
+
+    /// Sets what happens when two files have the same key with different values. The default is
+    /// ConflictPolicy::Ignore, where the file picked by fn overwrite_policy wins without a word. With
+    /// ConflictPolicy::Warn or ConflictPolicy::Error, a base file and an override file which drifted apart by
+    /// accident are noticed. Keys with the same value in both files, and repeated keys in a single file, are
+    /// not conflicts. The values are compared before interpolation. The values of keys passed to fn mark_secret
+    /// are shown as `***` in the warnings and the error. fn activate_streaming doesn't keep the values
+    /// it needs to compare, so it doesn't check for conflicts.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{ConflictPolicy, EnvLoader};
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .add_file(String::from("./.env_plus.local"))
+    ///     .conflict_policy(ConflictPolicy::Warn);
+    /// 
+    ///     loader.activate().unwrap();
+    /// 
+    ///     for warning in &loader.report().warnings {
+    ///         eprintln!("{}", warning);
+    ///     }
+    /// }
+    /// ```
+    pub fn conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+
+        self
+    }
+
+    /// If true is passed, whitespace around keys and values is removed, so indented lines and lines like
+    /// `KEY = value` load the key `KEY` with the value `value`. This covers tabs as well as spaces, no matter how
+    /// deeply a line is indented. Whitespace inside quotes is kept when fn parse_quotes is used.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// database:
+    ///     SECRET = YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .trim_whitespace(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn trim_whitespace(mut self, trim: bool) -> Self {
+        self.trim = trim;
+
+        self
+    }
+
+    /// Lines made of a single character repeated at least twice, such as `================`, are ignored when the
+    /// character is one of the given ones. This lets files use decorative separator lines which are not
+    /// comments. No characters are set by default, so such lines are reported as not formatted properly.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// ================
+    /// SECRET=YOUR_SECRET
+    /// ----------------
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .separator_chars(vec!['=', '-'])
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn separator_chars(mut self, chars: Vec<char>) -> Self {
+        self.separator_chars = chars;
+
+        self
+    }
+
+    /// Makes fn activate set only the given keys. The whole file is still parsed and checked, but every
+    /// other key in it is ignored. This is meant for refreshing a known set of variables from a file which
+    /// has more in it. The report of the load lists which of these keys were found in the file.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .overwrite_envs(true)
+    ///     .apply_only(vec![String::from("TOKEN"), String::from("SECRET")]);
+    /// 
+    ///     loader.activate().unwrap();
+    /// 
+    ///     for key in &loader.report().requested_missing {
+    ///         println!("{} is not in the file", key);
+    ///     }
+    /// }
+    /// ```
+    pub fn apply_only(mut self, keys: Vec<String>) -> Self {
+        self.apply_only = Some(keys);
+
+        self
+    }
+
+    /// Sets a callback which decides what happens when a line can't be parsed, instead of failing the whole
+    /// load. It is called with the line number, the content of the line and the error, and returns a Recovery
+    /// to skip the line, stop parsing or parse a corrected line in its place.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, Recovery};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .on_error(|line, content, err| {
+    ///         eprintln!("Skipping line {} ({}): {}", line, content, err);
+    ///         Recovery::Skip
+    ///     })
+    ///     .activate().unwrap();
+    /// }
+    /// ```
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(usize, &str, &EnvError) -> Recovery + Send + 'static,
+    {
+        self.on_error = Some(Arc::new(Mutex::new(handler)));
+
+        self
+    }
+
+    /// If true is passed, a line which doesn't look like a key is added to the value of the key before it,
+    /// joined with a newline. This is meant for multi-line blobs pasted into a file without any continuation
+    /// markers. A line looks like a key when it has the delimiter before any whitespace, ignoring indentation,
+    /// so `KEY=value` starts a new key while `some text` or `a = b` continue the previous value. Comments and
+    /// blank lines are skipped as usual, and a line which doesn't look like a key at the top of the file is
+    /// still an error.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// CERT=-----BEGIN CERTIFICATE-----
+    /// MIIBszCCAVmgAwIBAgIU
+    /// -----END CERTIFICATE-----
+    /// NEXT=value
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .greedy_values(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     let cert = std::env::var("CERT").unwrap();
+    ///     assert_eq!(cert.lines().count(), 3);
+    /// }
+    /// ```
+    pub fn greedy_values(mut self, greedy: bool) -> Self {
+        self.greedy_values = greedy;
+
+        self
+    }
+
+    /// If true is passed, a file which is not valid UTF-8 or which starts with a byte order mark fails to load
+    /// with an EnvError::Encoding, instead of having the mark removed. By default a leading UTF-8 byte order
+    /// mark is dropped before parsing and invalid UTF-8 is reported as an EnvError::Io. Use this when the
+    /// bytes of the file have to be loaded exactly as they are.
+    pub fn strict_utf8(mut self, strict: bool) -> Self {
+        self.strict_utf8 = strict;
+
+        self
+    }
+
+    /// If true is passed, `$NAME` in a value is replaced with the value of the key `NAME` from an earlier line.
+    /// This is a small and predictable form of substitution: it only happens when the `$` is the first character
+    /// of the value or comes right after a `/`, and the name is the run of ASCII letters, digits and underscores
+    /// after it. The key is looked up by the name it has in the file, before any of the key options are applied,
+    /// and a name which is not defined on an earlier line is left as it is.
+    /// 
+    /// References are replaced after the files are merged, so a key from an earlier file can be used in a
+    /// later one, and a reference always gets the value the key is loaded with. A key which is repeated
+    /// counts as defined where it first appears.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// BASE_PATH=/srv/app
+    /// FULL_PATH=$BASE_PATH/api/$VERSION
+    /// VERSION=v1
+    /// PRICE=5$USD
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .simple_interpolation(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     // VERSION comes after FULL_PATH and `$USD` doesn't follow a slash, so neither is replaced.
+    ///     assert_eq!(std::env::var("FULL_PATH").unwrap(), String::from("/srv/app/api/$VERSION"));
+    ///     assert_eq!(std::env::var("PRICE").unwrap(), String::from("5$USD"));
+    /// }
+    /// ```
+    pub fn simple_interpolation(mut self, interpolate: bool) -> Self {
+        self.simple_interpolation = interpolate;
+
+        self
+    }
+
+    /// If true is passed, `${NAME}` in a value is replaced with the value of `NAME`. The key is first looked
+    /// up among the earlier lines of the files, by the name it has in the file, and then in the environment.
+    /// A name which is in neither of them is replaced with an empty string, and a `${` without a closing `}`
+    /// is left as it is. Unlike fn simple_interpolation, `${NAME}` is replaced anywhere in the value. Like
+    /// that option, references are replaced after the files are merged, and can use keys from earlier files.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// HOST=localhost
+    /// URL=http:${SLASHES}${HOST}:${PORT}
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     std::env::set_var("PORT", "8080");
+    ///     std::env::set_var("SLASHES", "//");
+    /// 
+    ///     EnvLoader::new()
+    ///     .interpolate(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("URL").unwrap(), String::from("http://localhost:8080"));
+    /// }
+    /// ```
+    pub fn interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+
+        self
+    }
+
+    /// Sets the most bytes a single value may have after fn interpolate has replaced its references. A value
+    /// which goes over it fails the load with an EnvError::ExpansionTooLong naming the key and line, which
+    /// stops a file like `A=${B}${B}${B}` from using up memory when `B` is large. There is no limit by default.
+    pub fn max_expanded_value_length(mut self, limit: usize) -> Self {
+        self.max_expanded_length = Some(limit);
+
+        self
+    }
+
+    /// Makes a `${NAME}` from fn interpolate which can't be resolved fail with an EnvError::UnresolvedReference
+    /// instead of becoming an empty string. StrictInterpolation::Undefined only fails on names which are
+    /// neither an earlier key nor in the environment, while StrictInterpolation::UndefinedOrEmpty also fails
+    /// on ones which are set to an empty value.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// HOST=
+    /// URL=https:${HOST}
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, StrictInterpolation};
+    /// 
+    /// fn main() {
+    ///     let result = EnvLoader::new()
+    ///     .interpolate(true)
+    ///     .strict_interpolation(StrictInterpolation::UndefinedOrEmpty)
+    ///     .activate();
+    /// 
+    ///     assert!(result.is_err());
+    /// }
+    /// ```
+    pub fn strict_interpolation(mut self, level: StrictInterpolation) -> Self {
+        self.strict_interpolation = level;
+
+        self
+    }
+
+    /// If true is passed, fn activate measures how long it takes to read and parse the files and stores it
+    /// in LoadReport::elapsed. Setting the variables afterwards is not included, except with
+    /// fn activate_streaming, which sets every variable as soon as it is read.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .measure_load_time(true);
+    /// 
+    ///     loader.activate().unwrap();
+    /// 
+    ///     println!("Loaded the config in {:?}", loader.report().elapsed.unwrap());
+    /// }
+    /// ```
+    pub fn measure_load_time(mut self, measure: bool) -> Self {
+        self.measure_time = measure;
+
+        self
+    }
+
+    /// If true is passed, a value wrapped in brackets is loaded as a list. The items between the brackets are
+    /// split on the separator and have their whitespace trimmed, so by default `HOSTS=[a, b, c]` loads
+    /// `a,b,c`. The brackets, the separator and whether the items are set as separate variables are set with
+    /// fn list_format. Lists are read after the other value options, so a quoted list needs fn parse_quotes.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// HOSTS=[alpha, beta, gamma]
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .parse_bracketed_lists(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("HOSTS").unwrap(), String::from("alpha,beta,gamma"));
+    /// }
+    /// ```
+    pub fn parse_bracketed_lists(mut self, lists: bool) -> Self {
+        self.lists = lists;
+
+        self
+    }
+
+    /// Changes how fn parse_bracketed_lists reads and loads lists.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// HOSTS=(alpha; beta)
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, ListFormat};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .parse_bracketed_lists(true)
+    ///     .list_format(ListFormat { open: '(', close: ')', separator: ';', indexed: true, strict: false })
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("HOSTS_0").unwrap(), String::from("alpha"));
+    ///     assert_eq!(std::env::var("HOSTS_1").unwrap(), String::from("beta"));
+    /// }
+    /// ```
+    pub fn list_format(mut self, format: ListFormat) -> Self {
+        self.list_format = format;
+
+        self
+    }
+
+    /// If true is passed, a file which can be read by its group or by other users fails to load with an
+    /// EnvError::InsecurePermissions before anything is read from it, the same way ssh refuses key files
+    /// anyone can read. The check is only done on Unix, on other platforms this option does nothing.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     // Fails unless the file was made private with `chmod 600 .env_plus`.
+    ///     EnvLoader::new()
+    ///     .require_secure_permissions(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn require_secure_permissions(mut self, secure: bool) -> Self {
+        self.secure_permissions = secure;
+
+        self
+    }
+
+    /// Sets a callback which decides the path of the file when fn activate is called, instead of when the
+    /// loader is built. The path it returns replaces the files set so far, the same way fn change_file
+    /// does, and is the one shown in the report and in errors. An error from the callback stops fn activate
+    /// and is returned from it. The callback is only called once per loader, later calls to fn activate keep
+    /// using the path it returned. A clone made before that calls it again for itself. Only fn activate,
+    /// fn activate_with_progress and fn activate_streaming call it, so the other methods read the files set
+    /// before.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .file_resolver(|| {
+    ///         let region = std::env::var("REGION").unwrap_or_else(|_| String::from("eu"));
+    ///         Ok(format!("./config/{}.env", region))
+    ///     })
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn file_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: FnMut() -> Result<String, EnvError> + Send + 'static,
+    {
+        self.file_resolver = Some(Arc::new(Mutex::new(resolver)));
+
+        self
+    }
+
+    /// If true is passed, fn activate remembers a hash of the contents of the files and does nothing when
+    /// they are the same on the next call, which makes reloading a file that rarely changes cheap. The files
+    /// are still read to compare them, but not parsed or set. Whether a call was skipped is shown in
+    /// LoadReport::cache_hit. Only the contents are compared, so after changing the options of the loader
+    /// use fn force_reload.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .overwrite_envs(true)
+    ///     .skip_unchanged(true);
+    /// 
+    ///     loop {
+    ///         loader.activate().unwrap();
+    /// 
+    ///         if !loader.report().cache_hit {
+    ///             println!("The config was reloaded");
+    ///         }
+    /// 
+    ///         std::thread::sleep(std::time::Duration::from_secs(5));
+    ///     }
+    /// }
+    /// ```
+    pub fn skip_unchanged(mut self, skip: bool) -> Self {
+        self.skip_unchanged = skip;
+
+        self
+    }
+
+    /// Makes fn activate add the values of the given keys to the end of the ones already in the environment,
+    /// instead of replacing or skipping them. Every activation appends again, which suits variables that
+    /// record a history, like the configs which were loaded. The values are joined with the separator from
+    /// fn append_separator, a `,` by default. A key which is not set yet is set to its value as usual, and
+    /// overwriting doesn't affect these keys.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// LOADED_CONFIGS=base
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .append_across_reloads(vec![String::from("LOADED_CONFIGS")]);
+    /// 
+    ///     loader.activate().unwrap();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("LOADED_CONFIGS").unwrap(), String::from("base,base"));
+    /// }
+    /// ```
+    pub fn append_across_reloads(mut self, keys: Vec<String>) -> Self {
+        self.append_keys = keys;
+
+        self
+    }
+
+    /// Changes the separator fn append_across_reloads puts between the old and the new value.
+    pub fn append_separator(mut self, separator: String) -> Self {
+        self.append_separator = separator;
+
+        self
+    }
+
+    /// If true is passed, a value like `@json:secrets.json:db.password` is replaced with the field `db.password`
+    /// of the JSON file `secrets.json`, and `@toml:` does the same for TOML files. The path of the referenced
+    /// file is relative to the directory of the ENV file the value is in. The field is a path of keys joined
+    /// with dots, where a number picks an item of an array. A string field is loaded as it is and any other
+    /// field as its JSON or TOML text. A file which can't be read or parsed, or a field which is not in it, is
+    /// an EnvError::Reference with the line of the value.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// DB_PASSWORD=@json:secrets.json:db.password
+    /// FIRST_REPLICA=@toml:cluster.toml:replicas.0.host
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .resolve_file_references(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn resolve_file_references(mut self, resolve: bool) -> Self {
+        self.file_references = resolve;
+
+        self
+    }
+
+    /// Makes fn activate add up the size of the environment as it would be after the variables are set, and
+    /// warn or fail with an EnvError::EnvTooLarge before setting anything if it is over the limit. Each
+    /// variable counts as the bytes of its key and value, plus two for the `=` and the terminator. A process
+    /// can seem to load fine with a huge environment and only fail later when it spawns a child, so this
+    /// catches it where the variables come from.
+    /// 
+    /// The limit is 32767 bytes on Windows and 2 MiB elsewhere, which can be changed with fn env_size_limit.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, SizeLimitAction};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .check_env_size(SizeLimitAction::Error)
+    ///     .env_size_limit(64 * 1024)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn check_env_size(mut self, action: SizeLimitAction) -> Self {
+        self.env_size_action = Some(action);
+
+        self
+    }
+
+    /// Changes the number of bytes fn check_env_size allows the environment to have.
+    pub fn env_size_limit(mut self, bytes: usize) -> Self {
+        self.env_size_limit = bytes;
+
+        self
+    }
+
+    /// Adds delimiters which assign their value in a different way, like `?=` to only set a key which is not
+    /// set yet and `+=` to add to its value. The delimiter from fn change_delimiter stays in use and means
+    /// Assignment::Set, unless it is registered here with another meaning.
+    /// 
+    /// When more than one of them could split a line, the one which starts first on the line is used, so in
+    /// `KEY=a?=b` the value of `KEY` is `a?=b`. If several start at the same place, the longest one wins, and
+    /// between operators of the same length the one registered first does. This makes `KEY+=b` use `+=`
+    /// even when `+` is also registered.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// PATH_EXTRA=/opt/bin
+    /// PATH_EXTRA+=/usr/local/bin
+    /// LOG_LEVEL?=info
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{Assignment, EnvLoader};
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .assignment_operators(vec![
+    ///         (String::from("?="), Assignment::SetIfUnset),
+    ///         (String::from("+="), Assignment::Append),
+    ///     ])
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("PATH_EXTRA").unwrap(), String::from("/opt/bin,/usr/local/bin"));
+    /// }
+    /// ```
+    pub fn assignment_operators(mut self, operators: Vec<(String, Assignment)>) -> Self {
+        self.operators = operators.into_iter().map(|(operator, assignment)| (Marker::Text(operator), assignment)).collect();
+
+        self
+    }
+
+    /// Names the keys whose values are lists of paths, like `PATH`. Their paths are joined with `;` on Windows
+    /// and with `:` everywhere else, whichever one the file uses, so one file works on both. On Windows a colon
+    /// after a drive letter, like in `C:\tools`, is kept. The keys are matched after the key options are applied.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// TOOL_PATH=/opt/tool/bin:/usr/local/bin
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .normalize_path_lists(vec![String::from("TOOL_PATH")])
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     // "/opt/tool/bin;/usr/local/bin" on Windows
+    ///     println!("{}", std::env::var("TOOL_PATH").unwrap());
+    /// }
+    /// ```
+    pub fn normalize_path_lists(mut self, keys: Vec<String>) -> Self {
+        self.path_lists = keys;
+
+        self
+    }
+
+    /// Accepts any of the delimiters in a line, so a file can use `=` and `:` interchangeably. Each line is
+    /// split on the delimiter which starts first, so in `HOST=localhost:8080` the key is `HOST`. When several start
+    /// at the same place, like `:` and `:=` in `KEY:=value`, the longest one wins. The first delimiter replaces
+    /// the one from fn change_delimiter and is used for new lines written by a Document.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET=YOUR_SECRET
+    /// PORT:8080
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .delimiters(vec![String::from("="), String::from(":")])
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("PORT").unwrap(), String::from("8080"));
+    /// }
+    /// ```
+    pub fn delimiters(mut self, delimiters: Vec<String>) -> Self {
+        let mut delimiters = delimiters.into_iter().map(Marker::Text);
+
+        if let Some(first) = delimiters.next() {
+            self.value_delimiter = first;
+        }
+
+        self.delimiters = delimiters.collect();
+
+        self
+    }
+
+    /// Makes a value which is the marker followed by a name, like `&APP_HOME` with the marker `&`, load the value
+    /// of that name instead. A key earlier in the files is used first, then a variable from the environment.
+    /// Unlike fn interpolate, the whole value has to be the reference. When the name is not found, the key is
+    /// left out as if it wasn't in the file, so it is only an error if the key is required with fn require.
+    /// Passing None turns it off again.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// APP_HOME=/srv/app
+    /// LOG_DIR=&APP_HOME
+    /// CACHE_DIR=&XDG_CACHE_HOME
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .env_reference_marker(Some(String::from("&")))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("LOG_DIR").unwrap(), String::from("/srv/app"));
+    /// }
+    /// ```
+    pub fn env_reference_marker(mut self, marker: Option<String>) -> Self {
+        self.env_reference_marker = marker;
+
+        self
+    }
+
+    /// If true is passed, a `\r` which is not followed by a `\n` ends a line as well, like in files saved with
+    /// the line endings of classic Mac OS. Without it such a file is read as a single line. Lines ending with
+    /// `\r\n` are always split correctly.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .change_file(String::from("./classic_mac.env"))
+    ///     .treat_cr_as_newline(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn treat_cr_as_newline(mut self, treat: bool) -> Self {
+        self.cr_newlines = treat;
+
+        self
+    }
+
+    /// If true is passed, zero-width characters are removed from every value, wherever they are in it. These
+    /// often end up in values copied from web pages or chat messages, where they can't be seen but make the
+    /// value compare unequal. The removed code points are:
+    /// * U+200B zero width space
+    /// * U+200C zero width non-joiner
+    /// * U+200D zero width joiner
+    /// * U+2060 word joiner
+    /// * U+FEFF zero width no-break space, which is also the byte order mark
+    /// 
+    /// Keys are left as they are. A byte order mark at the start of the file is handled separately, see
+    /// fn strict_utf8.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .strip_invisible_chars(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn strip_invisible_chars(mut self, strip: bool) -> Self {
+        self.strip_invisible = strip;
+
+        self
+    }
+
+    /// Makes fn activate leave the given keys alone when they are already in the environment, even if
+    /// overwriting is on or the key is appended to. This is for values set by the program before the files are
+    /// loaded, which should win over the files while other keys can still be overwritten. A protected key which
+    /// is not set yet is loaded from the files as usual.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     std::env::set_var("PORT", "3000");
+    /// 
+    ///     EnvLoader::new()
+    ///     .overwrite_envs(true)
+    ///     .protect_existing(vec![String::from("PORT")])
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("PORT").unwrap(), String::from("3000"));
+    /// }
+    /// ```
+    pub fn protect_existing(mut self, keys: Vec<String>) -> Self {
+        self.protected = keys;
+
+        self
+    }
+
+    /// Makes loading fail with an EnvError::KeyTooLong when a key in the file is longer than the limit in bytes.
+    /// A key that long usually means a line is missing its delimiter, or the file is not an ENV file at all.
+    /// The key is measured as it is written in the file, before any of the key options change it.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .max_key_length(128)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn max_key_length(mut self, limit: usize) -> Self {
+        self.max_key_length = Some(limit);
+
+        self
+    }
+
+    /// If true is passed, a line ending with `\` continues on the next line. The `\` is removed and the lines
+    /// are joined with the string from fn continuation_join, a newline by default. Everything on the next line is
+    /// kept, including its indentation, and it can end with a `\` as well to continue further. Only a `\` at the
+    /// end of the value counts, so one at the end of a comment or of a comment line doesn't continue anything.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// MOTD=Welcome!\
+    /// Have a nice day.
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .line_continuation(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("MOTD").unwrap(), String::from("Welcome!\nHave a nice day."));
+    /// }
+    /// ```
+    pub fn line_continuation(mut self, continuation: bool) -> Self {
+        self.continuation = continuation;
+
+        self
+    }
+
+    /// Changes the string fn line_continuation puts between the lines of a value. An empty string joins them
+    /// directly, which suits long values like base64 blobs which are split over several lines.
+    pub fn continuation_join(mut self, join: String) -> Self {
+        self.continuation_join = join;
+
+        self
+    }
+
+    /// If true is passed, the first line of every file is ignored, whatever it contains. A first line which
+    /// starts with `#!` is always ignored, so a file can be a shell script with a shebang and an ENV file at
+    /// the same time. Line numbers still count the ignored line.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// [generated by deploy.sh]
+    /// SECRET=YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .skip_first_line(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn skip_first_line(mut self, skip: bool) -> Self {
+        self.skip_first_line = skip;
+
+        self
+    }
+
+    /// If true is passed, fn activate sets a key which is only in the environment with a different case under
+    /// the name from the environment, so `path` in the file updates an existing `PATH` instead of adding a
+    /// second variable. This is how Windows treats variable names anyway, so there it only changes the names
+    /// in the report. On other platforms names are case-sensitive, and this makes them behave the same way.
+    /// A key which matches more than one variable, like `Path` next to `PATH` and `path`, is left as it is.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// path=/opt/tool/bin
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .overwrite_envs(true)
+    ///     .match_existing_key_case(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("PATH").unwrap(), String::from("/opt/tool/bin"));
+    /// }
+    /// ```
+    pub fn match_existing_key_case(mut self, matching: bool) -> Self {
+        self.match_key_case = matching;
+
+        self
+    }
+
+    /// Removes the separator from the end of an unquoted value, together with the whitespace around it. The
+    /// comment is removed first, so `KEY=value ; // note` with `;` loads `value`. Only one separator is removed,
+    /// and quoted values are left as they are. Passing None turns it off again.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET=YOUR_SECRET; // the shared one
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .strip_trailing_separator(Some(';'))
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(std::env::var("SECRET").unwrap(), String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn strip_trailing_separator(mut self, separator: Option<char>) -> Self {
+        self.trailing_separator = separator;
+
+        self
+    }
+
+    /// Lets fn get_int read numbers written with a grouping character between every three digits, like
+    /// `1,000,000` with `,` or `1.000.000` with `.`, as some locales write them. The groups are checked before
+    /// the character is removed, so with `.` a value like `1.5` is still an EnvError::InvalidValue instead of
+    /// being read as `15`. Only plain decimal numbers can be grouped. Passing None turns it off again.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// MAX_CONNECTIONS=1.000.000
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .digit_grouping(Some('.'));
+    /// 
+    ///     loader.activate().unwrap();
+    ///     assert_eq!(loader.get_int("MAX_CONNECTIONS").unwrap(), 1_000_000);
+    /// }
+    /// ```
+    pub fn digit_grouping(mut self, separator: Option<char>) -> Self {
+        self.digit_grouping = separator;
+
+        self
+    }
+
+    /// Removes a variable from the environment instead of setting it when its value is exactly the token, like
+    /// `NONE`. This lets a later file clear a variable set by an earlier one. A value is only removed when it
+    /// would have been set, so a variable which already exists is only removed when overwriting is on. The
+    /// token is compared after quotes and comments are removed, so an empty token matches `KEY=`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// DEBUG_PORT=NONE
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     std::env::set_var("DEBUG_PORT", "9229");
+    /// 
+    ///     EnvLoader::new()
+    ///     .unset_value_token(String::from("NONE"))
+    ///     .overwrite_envs(true)
+    ///     .activate()
+    ///     .unwrap();
+    /// 
+    ///     assert!(std::env::var("DEBUG_PORT").is_err());
+    /// }
+    /// ```
+    pub fn unset_value_token(mut self, token: String) -> Self {
+        self.unset_token = Some(token);
+
+        self
+    }
+
+    /// Keeps the empty items fn get_list finds between two separators in a row, or after a separator at the
+    /// end, instead of dropping them. A variable which is empty is still an empty list.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     std::env::set_var("COLUMNS", "id,,name");
+    /// 
+    ///     let loader = EnvLoader::new()
+    ///     .keep_empty_list_items(true);
+    /// 
+    ///     assert_eq!(loader.get_list("COLUMNS", ",").unwrap(), vec!["id", "", "name"]);
+    /// }
+    /// ```
+    pub fn keep_empty_list_items(mut self, keep: bool) -> Self {
+        self.keep_empty_items = keep;
+
+        self
+    }
+
+    /// Parse the file and return its keys and values without setting anything in the environment.
+    /// When a key is in the file more than once, the value that fn activate would end up setting is used.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let vars = EnvLoader::new()
+    ///     .parse()
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(vars.get("SECRET"), Some(&String::from("YOUR_SECRET")));
+    /// }
+    /// ```
+    pub fn parse(&self) -> Result<HashMap<String, String>, EnvError> {
+        Ok(self.parse_ordered()?.into_iter().collect())
+    }
+
+    /// The same as fn parse, but the keys are returned in the order they first appear in the file.
+    pub fn parse_ordered(&self) -> Result<Vec<(String, String)>, EnvError> {
+        let entries = load_entries(self)?;
+
+        Ok(entries.into_iter().map(|entry| (entry.key.into_owned(), entry.value.into_owned())).collect())
+    }
+
+    /// Reads every file and keeps their contents, so they can be parsed with fn FileBuffers::parse. The
+    /// keys and values it returns borrow from the buffers where they can, which avoids a new string for
+    /// every value of a file that is parsed again and again. The options are copied from this loader when
+    /// the files are read, and changing the loader afterwards doesn't affect the buffers.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use std::borrow::Cow;
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let buffers = EnvLoader::new()
+    ///     .read_buffers()
+    ///     .unwrap();
+    /// 
+    ///     // The entries borrow from the buffers, so they have to be dropped first.
+    ///     let entries = buffers.parse().unwrap();
+    /// 
+    ///     for (key, value) in &entries {
+    ///         if let Cow::Borrowed(value) = value {
+    ///             println!("{} was not copied: {}", key, value);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn read_buffers(&self) -> Result<FileBuffers, EnvError> {
+        let files = self.files.iter().map(|path| read_file(path, self)).collect::<Result<_, _>>()?;
+
+        Ok(FileBuffers { files, loader: self.clone() })
+    }
+
+    /// Compares two files and returns the keys which were added, removed or changed between them. Both
+    /// files are parsed with the current options, so the comparison is between the values fn activate would
+    /// set. Removed and changed keys come first, in the order of the old file, followed by the added keys in
+    /// the order of the new file, so the same two files always give the same list.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvChange, EnvLoader};
+    /// 
+    /// fn main() {
+    ///     let changes = EnvLoader::new()
+    ///     .diff_files("./.env_old", "./.env_plus")
+    ///     .unwrap();
+    /// 
+    ///     for change in changes {
+    ///         match change {
+    ///             EnvChange::Added { key, value } => println!("+ {}={}", key, value),
+    ///             EnvChange::Removed { key, value } => println!("- {}={}", key, value),
+    ///             EnvChange::Changed { key, old, new } => println!("~ {}: {} -> {}", key, old, new),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn diff_files(&self, old: &str, new: &str) -> Result<Vec<EnvChange>, EnvError> {
+        let old = self.clone().change_file(old.to_string()).parse_ordered()?;
+        let new = self.clone().change_file(new.to_string()).parse_ordered()?;
+
+        Ok(diff_pairs(&old, &new))
+    }
+
+    /// Parse the file and return its contents together with every entry and where it was found.
+    /// This is meant for tools which show the file and the parsed values next to each other, as they
+    /// don't need to read the file a second time. Only the first file is read when there are more
+    /// of them, and an EnvError::NoFiles is returned when there are none. Nothing is set in the environment.
+    /// The keys and values are changed the same way fn activate changes them, like with fn strip_prefix or
+    /// fn strip_invisible_chars, but they are not interpolated.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let parsed = EnvLoader::new()
+    ///     .parse_with_source()
+    ///     .unwrap();
+    /// 
+    ///     for entry in &parsed.entries {
+    ///         println!("{}: {}", entry.line, &parsed.source[entry.span.clone()]);
+    ///     }
+    /// 
+    ///     println!("SECRET was written as {}", parsed.raw_lines()["SECRET"]);
+    /// }
+    /// ```
+    pub fn parse_with_source(&self) -> Result<ParsedSource, EnvError> {
+        let path = self.files.first().ok_or(EnvError::NoFiles)?;
+        let source = read_file(path, self)?;
+
+        let entries = parse_source(&source, 0, self)?
+            .into_iter()
+            .map(|entry| {
+                let key = transform_key(entry.key, self).into_owned();
+                let value = transform_value(&key, entry.value, self).into_owned();
+
+                SourceEntry { key, value, line: entry.line, span: entry.span }
+            })
+            .collect();
+
+        Ok(ParsedSource { source, entries, overwrite: self.overwrite })
+    }
+
+    /// Parse the file and return every entry together with the comment lines directly above it, which
+    /// turns a well commented file into documentation for its config. Nothing is set in the environment.
+    /// 
+    /// A comment belongs to the entry right below it. Empty lines separate the file into groups, so a comment
+    /// followed by an empty line is not attached to any entry and is returned in Documentation::comments
+    /// instead, as is a comment after the last entry. Comments at the end of a line are not included.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// // The port the server listens on.
+    /// // Ports below 1024 need root.
+    /// PORT=8080
+    /// 
+    /// // TODO: move the database settings to their own file.
+    /// 
+    /// DATABASE_URL=postgres://localhost
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let documentation = EnvLoader::new()
+    ///     .parse_documented()
+    ///     .unwrap();
+    /// 
+    ///     let port = &documentation.entries[0];
+    ///     assert_eq!(port.key, "PORT");
+    ///     assert_eq!(port.doc, vec!["The port the server listens on.", "Ports below 1024 need root."]);
+    /// 
+    ///     assert!(documentation.entries[1].doc.is_empty());
+    ///     assert_eq!(documentation.comments[0].lines, vec!["TODO: move the database settings to their own file."]);
+    /// }
+    /// ```
+    pub fn parse_documented(&self) -> Result<Documentation, EnvError> {
+        let comment = &self.comment;
+        let mut documentation = Documentation::default();
+
+        for path in &self.files {
+            let file = read_file(path, self)?;
+            let mut doc: Option<CommentBlock> = None;
+
+            for (ind, line) in records(&file, self).into_iter().enumerate() {
+                if ind == 0 && skips_first_line(line, self) { continue };
+
+                let trimmed = line.trim();
+
+                if trimmed.is_empty() {
+                    documentation.comments.extend(doc.take());
+                } else if let Some(text) = comment.strip_prefix(trimmed) {
+                    doc.get_or_insert_with(|| CommentBlock { line: ind + 1, lines: Vec::new() })
+                    .lines
+                    .push(text.trim().to_string());
+                } else if let Some(entry) = load_line(line, self, ind)? {
+                    documentation.entries.push(DocumentedEntry {
+                        key: transform_key(entry.key, self).into_owned(),
+                        value: entry.value.into_owned(),
+                        line: entry.line,
+                        doc: doc.take().map(|block| block.lines).unwrap_or_default(),
+                    });
+                }
+            }
+
+            documentation.comments.extend(doc);
+        }
+
+        Ok(documentation)
+    }
+
+    /// Returns the variables a program would see after loading the file, without changing the environment.
+    /// The current environment and the file are merged, and the precedence decides which value is used
+    /// for keys found in both of them.
+    /// 
+    /// Variables in the environment which are not valid unicode are left out.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, Precedence};
+    /// 
+    /// fn main() {
+    ///     std::env::set_var("SECRET", "MY_SECRET");
+    /// 
+    ///     let vars = EnvLoader::new()
+    ///     .merged_with_env(Precedence::FilePreferred)
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(vars.get("SECRET"), Some(&String::from("YOUR_SECRET")));
+    ///     assert!(vars.contains_key("PATH"));
+    /// }
+    /// ```
+    pub fn merged_with_env(&self, precedence: Precedence) -> Result<HashMap<String, String>, EnvError> {
+        let mut vars: HashMap<String, String> = std::env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+
+        for (key, value) in self.parse_ordered()? {
+            match precedence {
+                Precedence::FilePreferred => { vars.insert(key, value); },
+                Precedence::OsPreferred => { vars.entry(key).or_insert(value); },
+            }
+        }
+
+        Ok(vars)
+    }
+
+    /// Returns the keys and values of the files as PowerShell statements like `$env:KEY = 'value'`, one per line
+    /// in file order, which can be run with `Invoke-Expression`. Values are single quoted, so nothing in them is
+    /// expanded, and quotes in them are doubled. Keys which PowerShell doesn't accept after `$env:` are written
+    /// like `${env:MY-KEY}`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// GREETING=It's me
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let script = EnvLoader::new().to_powershell().unwrap();
+    /// 
+    ///     assert_eq!(script, String::from("$env:GREETING = 'It''s me'\n"));
+    /// }
+    /// ```
+    pub fn to_powershell(&self) -> Result<String, EnvError> {
+        let mut script = String::new();
+
+        for (key, value) in self.parse_ordered()? {
+            let variable = if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                format!("$env:{}", key)
+            } else {
+                format!("${{env:{}}}", key.replace('`', "``").replace('{', "`{").replace('}', "`}"))
+            };
+
+            // PowerShell also closes a single quoted string on the typographic single quotes.
+            let mut quoted = String::with_capacity(value.len() + 2);
+
+            for c in value.chars() {
+                if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') { quoted.push(c) };
+                quoted.push(c);
+            }
+
+            script.push_str(&format!("{} = '{}'\n", variable, quoted));
+        }
+
+        Ok(script)
+    }
+
+    /// Returns the keys and values of the files as `KEY=value` lines sorted by key, with the values of the
+    /// secret keys replaced by `***`, so the loaded configuration can be logged safely. Keys marked with
+    /// fn mark_secret are hidden as well. Nothing is set in the environment.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET=YOUR_SECRET
+    /// PORT=8080
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let dump = EnvLoader::new().dump_redacted(&[String::from("SECRET")]).unwrap();
+    /// 
+    ///     assert_eq!(dump, String::from("PORT=8080\nSECRET=***\n"));
+    /// }
+    /// ```
+    pub fn dump_redacted(&self, secret_keys: &[String]) -> Result<String, EnvError> {
+        let mut vars = self.parse_ordered()?;
+        vars.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let secret = |key: &str| {
+            #[cfg(feature = "zeroize")]
+            if self.secrets.iter().any(|secret| secret == key) { return true };
+
+            secret_keys.iter().any(|secret| secret == key)
+        };
+
+        let mut dump = String::new();
+
+        for (key, value) in &vars {
+            let value = if secret(key) { "***" } else { value.as_str() };
+            dump.push_str(&format!("{}={}\n", key, value));
+        }
+
+        Ok(dump)
+    }
+
+    /// Checks that every `${NAME}` in the files can be resolved, the way fn interpolate would resolve it, and
+    /// returns an EnvError::UnresolvedReference for each one which can't, with its line. A name resolves to a
+    /// key earlier in the files or to a variable in the environment. With StrictInterpolation::UndefinedOrEmpty
+    /// from fn strict_interpolation, names whose value is empty are reported as well. Nothing is set in the
+    /// environment, and fn interpolate doesn't need to be on.
+    /// 
+    /// An error which stops the files from loading is returned on its own.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// HOST=localhost
+    /// URL=http:${HOST}:${PORT}
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     if let Err(errors) = EnvLoader::new().check_interpolation() {
+    ///         for err in &errors {
+    ///             eprintln!("{}", err);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn check_interpolation(&self) -> Result<(), Vec<EnvError>> {
+        let mut raw = self.clone();
+        raw.interpolate = false;
+        raw.simple_interpolation = false;
+
+        let entries = load_entries(&raw).map_err(|err| vec![err])?;
+        let mut earlier: HashMap<String, String> = HashMap::new();
+        let mut errors = Vec::new();
+
+        for entry in &entries {
+            let mut rest = entry.value.as_ref();
+
+            while let Some(start) = rest.find("${") {
+                let end = match rest[start..].find('}') {
+                    Some(end) => start + end,
+                    None => break,
+                };
+
+                let name = &rest[start + 2..end];
+                let value = lookup(&earlier, name, self).cloned().or_else(|| std::env::var(name).ok());
+
+                let unresolved = match self.strict_interpolation {
+                    StrictInterpolation::UndefinedOrEmpty => value.as_deref().is_none_or(str::is_empty),
+                    _ => value.is_none(),
+                };
+
+                if unresolved {
+                    errors.push(EnvError::UnresolvedReference { line: entry.line, key: entry.key.to_string(), name: name.to_string() });
+                }
+
+                rest = &rest[end + 1..];
+            }
+
+            earlier.insert(entry.key.to_string(), entry.value.to_string());
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Returns every key given to fn require with where its value comes from, in the order they were given.
+    /// A key which is both in the files and in the environment counts as RequireSource::File. Missing keys are
+    /// included instead of returned as an error, so this shows all of them at once. Nothing is set in the
+    /// environment.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, RequireSource};
+    /// 
+    /// fn main() {
+    ///     let status = EnvLoader::new()
+    ///     .require(vec![String::from("SECRET"), String::from("HOME")])
+    ///     .require_status()
+    ///     .unwrap();
+    /// 
+    ///     for (key, source) in &status {
+    ///         if *source == RequireSource::Missing {
+    ///             eprintln!("{} is not set anywhere", key);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn require_status(&self) -> Result<Vec<(String, RequireSource)>, EnvError> {
+        let mut unchecked = self.clone();
+        unchecked.required = Vec::new();
+
+        let entries = load_entries(&unchecked)?;
+
+        Ok(self
+            .required
+            .iter()
+            .map(|key| {
+                let source = if entries.iter().any(|entry| entry.key == key.as_str()) {
+                    RequireSource::File
+                } else if std::env::var_os(key).is_some() {
+                    RequireSource::Environment
+                } else {
+                    RequireSource::Missing
+                };
+
+                (key.clone(), source)
+            })
+            .collect())
+    }
+
+    /// Checks the file against a schema and returns every problem at once, instead of stopping at the first
+    /// one. A field which is required has to be in the file or in the environment, or an
+    /// EnvError::MissingRequired is returned for it. A field which is set has to parse as its kind and be one
+    /// of its allowed values, or an EnvError::InvalidValue is returned. A value from the environment is only
+    /// checked when the key is not in the file, and is reported with line 0. Keys which are not in the schema
+    /// are not checked. An error which stops parsing, like a malformed line, is returned on its own. Nothing
+    /// is set in the environment.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, FieldKind, FieldSpec};
+    /// 
+    /// fn main() {
+    ///     let schema = vec![
+    ///         FieldSpec { name: String::from("PORT"), required: true, kind: FieldKind::Integer, ..Default::default() },
+    ///         FieldSpec {
+    ///             name: String::from("LOG_LEVEL"),
+    ///             allowed_values: vec![String::from("debug"), String::from("info")],
+    ///             ..Default::default()
+    ///         },
+    ///     ];
+    /// 
+    ///     if let Err(errors) = EnvLoader::new().validate_schema(&schema) {
+    ///         for err in errors {
+    ///             eprintln!("{}", err);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn validate_schema(&self, schema: &[FieldSpec]) -> Result<(), Vec<EnvError>> {
+        let entries = load_entries(self).map_err(|err| vec![err])?;
+        let mut errors = Vec::new();
+
+        for field in schema {
+            let (line, value) = match entries.iter().find(|entry| entry.key == field.name) {
+                Some(entry) => (entry.line, entry.value.to_string()),
+                None => match std::env::var(&field.name) {
+                    Ok(value) => (0, value),
+                    Err(_) => {
+                        if field.required {
+                            errors.push(EnvError::MissingRequired { key: field.name.clone(), suggestion: None });
+                        }
+                        continue;
+                    },
+                },
+            };
+
+            let parses = match field.kind {
+                FieldKind::String => true,
+                FieldKind::Integer => value.parse::<i64>().is_ok(),
+                FieldKind::Float => value.parse::<f64>().is_ok(),
+                FieldKind::Bool => value.parse::<bool>().is_ok(),
+            };
+
+            if !parses {
+                let message = format!("'{}' is not a valid {:?}", value, field.kind);
+                errors.push(EnvError::InvalidValue { line, key: field.name.clone(), message });
+            } else if !field.allowed_values.is_empty() && !field.allowed_values.contains(&value) {
+                let message = format!("'{}' is not one of {}", value, field.allowed_values.join(", "));
+                errors.push(EnvError::InvalidValue { line, key: field.name.clone(), message });
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Changes the value of a key in a file on disk and leaves the rest of the file as it is, see Document.
+    /// Every line with the key gets the new value, and the key is added at the end of the file when it is
+    /// not in it yet. The file is written to a new temporary file next to it first, which only the owner can
+    /// read until it gets the permissions of the file and replaces it, so a failed write never leaves half a
+    /// file behind. The new value is written exactly as it is given.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .update_key_in_file("./.env_plus", "API_TOKEN", "rotated-token")
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn update_key_in_file(&self, path: &str, key: &str, new_value: &str) -> Result<(), EnvError> {
+        let source = read_file(path, self)?;
+
+        let mut document = Document::parse(&source, self);
+        document.set(key, new_value);
+
+        static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+        let io = |source| EnvError::Io { path: path.to_string(), source };
+        let permissions = fs::metadata(path).map_err(io)?.permissions();
+        let temp = format!("{}.{}.{}.tmp", path, std::process::id(), TEMP_FILES.fetch_add(1, Ordering::Relaxed));
+
+        // A new file which only the owner can read, so the values never sit in a file others can open.
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let written = options.open(&temp)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, document.to_string().as_bytes()))
+            .and_then(|_| fs::set_permissions(&temp, permissions))
+            .and_then(|_| fs::rename(&temp, path));
+
+        if written.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+
+        written.map_err(io)
+    }
+
+    /// Returns the environment which fn activate would leave behind if the environment was start, without
+    /// reading or changing the real one. Keys in start count as already set, so overwriting, fn append_across_reloads
+    /// and fn assignment_operators treat them the same way they would treat variables in the environment. They
+    /// also count for fn require, references and fn match_existing_key_case, and fn check_env_size checks the
+    /// size of the result. fn apply_only works as usual. A variable which is only in the real environment is not
+    /// seen by any of them, nor by fn expand_paths, so the result doesn't depend on the process.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// SECRET=YOUR_SECRET
+    /// PORT=8080
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use std::collections::HashMap;
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let start = HashMap::from([(String::from("PORT"), String::from("3000"))]);
+    ///     let result = EnvLoader::new().apply_to(start).unwrap();
+    /// 
+    ///     assert_eq!(result["SECRET"], String::from("YOUR_SECRET"));
+    ///     assert_eq!(result["PORT"], String::from("3000"));
+    /// }
+    /// ```
+    pub fn apply_to(mut self, start: HashMap<String, String>) -> Result<HashMap<String, String>, EnvError> {
+        self.context = Some(start);
+
+        let mut entries = load_entries(&self)?;
+        let mut result = self.context.take().unwrap_or_default();
+
+        let lengths = result.iter().map(|(key, value)| (key.clone(), value.len())).collect();
+        prepare_entries(&self, &mut entries, &lengths, &mut LoadReport::default())?;
+
+        let existing: HashSet<String> = result.keys().cloned().collect();
+
+        apply_entries(&self, &entries, |key| existing.contains(key), |entry| {
+            if unsets(&self, entry) {
+                result.remove(entry.key.as_ref());
+                return
+            }
+
+            let value = match result.get(entry.key.as_ref()) {
+                Some(old) if appends(&self, entry) => {
+                    format!("{}{}{}", old, self.append_separator, entry.value)
+                },
+                _ => entry.value.to_string(),
+            };
+
+            result.insert(entry.key.to_string(), value);
+        });
+
+        Ok(result)
+    }
+
+    /// Adds the variables from the file to a command, without changing the environment of the current
+    /// process. The command still has to be spawned by the caller. A key which the command would already
+    /// inherit, either from the current process or from an earlier call to `Command::env`, is only replaced
+    /// when overwriting is on, the same way fn activate treats the current environment. A variable removed
+    /// with `Command::env_remove` counts as unset. `Command::env_clear` can't be detected, so after it the
+    /// variables of the current process still count as set, and overwriting should be on. fn apply_only,
+    /// fn match_existing_key_case and fn check_env_size work over the variables the command would inherit.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use std::process::Command;
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut command = Command::new("./server");
+    /// 
+    ///     EnvLoader::new()
+    ///     .run_command(&mut command)
+    ///     .unwrap();
+    /// 
+    ///     command.status().unwrap();
+    /// }
+    /// ```
+    pub fn run_command(&self, cmd: &mut std::process::Command) -> Result<(), EnvError> {
+        let mut entries = load_entries(self)?;
+
+        let explicit: HashMap<std::ffi::OsString, Option<std::ffi::OsString>> = cmd
+            .get_envs()
+            .map(|(key, value)| (key.to_os_string(), value.map(std::ffi::OsStr::to_os_string)))
+            .collect();
+
+        let mut lengths = env_lengths();
+
+        for (key, value) in &explicit {
+            let key = key.to_string_lossy().into_owned();
+
+            match value {
+                Some(value) => lengths.insert(key, value.len()),
+                None => lengths.remove(&key),
+            };
+        }
+
+        prepare_entries(self, &mut entries, &lengths, &mut LoadReport::default())?;
+
+        let current = |key: &str| match explicit.get(std::ffi::OsStr::new(key)) {
+            Some(value) => value.clone(),
+            None => std::env::var_os(key),
+        };
+
+        apply_entries(self, &entries, |key| current(key).is_some(), |entry| {
+            if unsets(self, entry) {
+                cmd.env_remove(entry.key.as_ref());
+                return
+            }
+
+            match current(&entry.key) {
+                Some(mut existing) if appends(self, entry) => {
+                    existing.push(&self.append_separator);
+                    existing.push(entry.value.as_ref());
+                    cmd.env(entry.key.as_ref(), existing);
+                },
+                _ => { cmd.env(entry.key.as_ref(), entry.value.as_ref()); },
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Read the file as a list of flags, where a line with just a name in it means the flag is enabled.
+    /// Comments work as usual and lines with a key and a value are not flags, so they are left out.
+    /// Nothing is set in the environment.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // flags.env
+    /// 
+    /// NEW_DASHBOARD
+    /// BETA_SEARCH // Remove after the release
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let flags = EnvLoader::new()
+    ///     .change_file(String::from("./flags.env"))
+    ///     .parse_flags()
+    ///     .unwrap();
+    /// 
+    ///     assert!(flags.contains("NEW_DASHBOARD"));
+    ///     assert!(flags.contains("BETA_SEARCH"));
+    /// }
+    /// ```
+    pub fn parse_flags(&self) -> Result<HashSet<String>, EnvError> {
+        let mut flags = HashSet::new();
+
+        for path in &self.files {
+            let file = read_file(path, self)?;
+
+            for (ind, line) in split_lines(&file, self).into_iter().enumerate() {
+                if ind == 0 && skips_first_line(line, self) { continue };
+
+                let main_line = match strip_line(line, self) {
+                    Some(main_line) => main_line.trim(),
+                    None => continue,
+                };
+
+                if main_line.is_empty() || find_delimiter(main_line, self).is_some() { continue };
+
+                flags.insert(main_line.to_string());
+            }
+        }
+
+        Ok(flags)
+    }
+
+
+    /// Checks the formatting of every file and returns the problems it finds: lines which end with whitespace,
+    /// indentation with both spaces and tabs, and a file which doesn't end with a line break. Only the text of
+    /// the files is checked, their entries are not parsed, so this can run as a lint on committed files.
+    /// An error is returned if a file can't be read.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let warnings = EnvLoader::new()
+    ///     .check_formatting()
+    ///     .unwrap();
+    /// 
+    ///     for warning in &warnings {
+    ///         eprintln!("{}:{}: {:?}", warning.file, warning.line, warning.issue);
+    ///     }
+    /// 
+    ///     assert!(warnings.is_empty());
+    /// }
+    /// ```
+    pub fn check_formatting(&self) -> Result<Vec<FormatWarning>, EnvError> {
+        let mut warnings = Vec::new();
+
+        for path in &self.files {
+            let file = read_file(path, self)?;
+            let mut warn = |line, issue| warnings.push(FormatWarning { file: path.clone(), line, issue });
+            let mut count = 0;
+
+            for (ind, line) in file.lines().enumerate() {
+                count = ind + 1;
+
+                if line.ends_with([' ', '\t']) {
+                    warn(count, FormatIssue::TrailingWhitespace);
+                }
+
+                let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+                if indent.contains(' ') && indent.contains('\t') {
+                    warn(count, FormatIssue::MixedIndentation);
+                }
+            }
+
+            if !file.is_empty() && !file.ends_with('\n') {
+                warn(count, FormatIssue::MissingFinalNewline);
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Returns a hash of the keys and values in the file, which changes whenever the loaded config changes.
+    /// The order of the lines doesn't matter and the same config always gives the same hash, in every run
+    /// and on every platform, so it can be stored and compared later.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let loader = EnvLoader::new();
+    ///     let before = loader.config_hash().unwrap();
+    /// 
+    ///     // ... the file is edited ...
+    /// 
+    ///     if loader.config_hash().unwrap() != before {
+    ///         println!("The config has changed");
+    ///     }
+    /// }
+    /// ```
+    pub fn config_hash(&self) -> Result<u64, EnvError> {
+        let mut pairs = self.parse_ordered()?;
+        pairs.sort();
+
+        let mut hash = FNV_OFFSET;
+        for (key, value) in pairs {
+            hash = fnv1a(hash, key.as_bytes());
+            hash = fnv1a(hash, &[0]);
+            hash = fnv1a(hash, value.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+
+        Ok(hash)
+    }
+
+    /// Parse the file and pass every key and value to the sink, in the order they are in the file.
+    /// Nothing is set in the environment. If the sink returns an error, an EnvError::Sink with the
+    /// line of the entry is returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, TypedSink};
+    /// 
+    /// #[derive(Default)]
+    /// struct Config {
+    ///     port: u16,
+    /// }
+    /// 
+    /// impl TypedSink for Config {
+    ///     fn set_typed(&mut self, key: &str, value: &str) -> Result<(), String> {
+    ///         if key == "PORT" {
+    ///             self.port = value.parse().map_err(|_| String::from("PORT must be a number"))?;
+    ///         }
+    /// 
+    ///         Ok(())
+    ///     }
+    /// }
+    /// 
+    /// fn main() {
+    ///     let mut config = Config::default();
+    /// 
+    ///     EnvLoader::new()
+    ///     .activate_into_typed(&mut config)
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn activate_into_typed<S: TypedSink>(&self, sink: &mut S) -> Result<(), EnvError> {
+        for entry in load_entries(self)? {
+            sink.set_typed(&entry.key, &entry.value).map_err(|message| EnvError::Sink {
+                line: entry.line,
+                key: entry.key.to_string(),
+                message,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Activate the module and load your ENV file.
+    /// 
+    /// # Examples 
+    /// 
+    /// ```text
+    /// // special.env 
+    /// 
+    /// @ I really love my comment design.
+    /// SECRET||YOUR_SECRET
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .change_delimiter(String::from("||"))
+    ///     .change_comment(String::from("@"))
+    ///     .change_file(String::from("./special.env"))
+    ///     .activate().unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    /// 
+    /// The variables are set in the order they first appear in the file, so anything reading them as they are
+    /// set sees them in file order.
+    /// 
+    /// An error is returned if the file can't be read or one of its lines is not formatted properly.
+    /// Nothing is set in that case.
+    /// 
+    /// The loader can be activated more than once. Each call replaces the report from fn report with a new
+    /// one for that call only, while fn sources keeps the keys of every earlier call, since the variables
+    /// they set are still in the environment. Use fn reset_state to start over.
+    pub fn activate(&mut self) -> Result<(), EnvError> {
+        self.activate_with_progress(|_| {})
+    }
+
+    /// Does the same as fn activate, calling the callback as the load goes on: before and after each file is
+    /// read, and for every key right after it is set. The keys are set once all the files are read, so the
+    /// events of every file come before the first ProgressEvent::KeySet. When the load fails, or is skipped by
+    /// fn skip_unchanged, there are no events after the point where it stopped.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::{EnvLoader, ProgressEvent};
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .add_file(String::from("./.env_plus.local"));
+    /// 
+    ///     loader.activate_with_progress(|event| match event {
+    ///         ProgressEvent::FileStarted(path) => println!("Reading {}", path.display()),
+    ///         ProgressEvent::FileFinished(path, count) => println!("Read {} keys from {}", count, path.display()),
+    ///         ProgressEvent::KeySet(key) => println!("Set {}", key),
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn activate_with_progress<F: FnMut(ProgressEvent)>(&mut self, mut progress: F) -> Result<(), EnvError> {
+        self.resolve_file()?;
+
+        self.report = LoadReport { files: self.files.iter().map(PathBuf::from).collect(), ..LoadReport::default() };
+
+        let mut content_hash = None;
+
+        if self.skip_unchanged {
+            let hash = match contents_hash(self) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    self.report.errors.push(err.to_string());
+                    return Err(err)
+                },
+            };
+
+            if self.content_hash == Some(hash) {
+                self.report.cache_hit = true;
+                return Ok(())
+            }
+
+            content_hash = Some(hash);
+        }
+
+        let start = Instant::now();
+        let mut warnings = Vec::new();
+        let loaded = load_file_with_progress(self, &mut progress).and_then(|parsed| process_entries(self, parsed, &mut warnings));
+
+        self.report.elapsed = if self.measure_time { Some(start.elapsed()) } else { None };
+        self.report.warnings = warnings;
+
+        let mut entries = match loaded {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.report.errors.push(err.to_string());
+                return Err(err)
+            },
+        };
+
+        let mut report = std::mem::take(&mut self.report);
+        let prepared = prepare_entries(self, &mut entries, &env_lengths(), &mut report);
+        self.report = report;
+
+        if let Err(err) = prepared {
+            self.report.errors.push(err.to_string());
+            return Err(err)
+        }
+
+        let keys: Vec<String> = entries.iter().map(|entry| entry.key.to_string()).collect();
+
+        // Compared in place, so the values of secrets are not copied where they can't be zeroized.
+        self.report.redundant = entries
+            .iter()
+            .filter(|entry| std::env::var_os(entry.key.as_ref()).is_some_and(|current| current == entry.value.as_ref()))
+            .map(|entry| entry.key.to_string())
+            .collect();
+
+        let (set, unset) = set_entries(self, entries, &mut progress);
+
+        self.report.parsed = keys.len();
+        self.report.set = set.iter().map(|(key, _)| key.clone()).collect();
+        self.report.skipped = keys.into_iter().filter(|key| !self.report.set.contains(key) && !unset.contains(key)).collect();
+        self.report.unset = unset;
+
+        for (key, file) in set {
+            if let Some(path) = self.files.get(file) {
+                self.sources.insert(key, PathBuf::from(path));
+            }
+        }
+
+        self.content_hash = content_hash;
+
+        Ok(())
+    }
+
+    /// Does the same as fn activate, but reads the files one line at a time and sets every variable as soon as
+    /// its line is read, so only the current line is kept in memory instead of the whole file. This is meant
+    /// for very large files. With fn greedy_values, a value is set once the next key or the end of the file is
+    /// reached, so only that value is buffered.
+    /// 
+    /// Because nothing waits for the end of the file, a key which is in the file more than once, even in
+    /// different files, is treated like a variable which is already set, and an error stops the load with the
+    /// lines before it already set. The options which need the whole file are not used: fn require, fn alias,
+    /// fn interpolate, fn simple_interpolation, fn apply_only, fn overwrite_policy, fn conflict_policy,
+    /// fn record_separator, fn skip_unchanged, fn check_env_size, fn line_continuation and the values set by a
+    /// Profile. A reference from fn env_reference_marker to an earlier key gets the value that key has in the
+    /// environment, and fn match_existing_key_case also matches the keys set by earlier lines.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .change_file(String::from("./huge.env"));
+    /// 
+    ///     loader.activate_streaming().unwrap();
+    ///     println!("Set {} variables", loader.report().set.len());
+    /// }
+    /// ```
+    pub fn activate_streaming(&mut self) -> Result<(), EnvError> {
+        self.resolve_file()?;
+
+        self.report = LoadReport { files: self.files.iter().map(PathBuf::from).collect(), ..LoadReport::default() };
+
+        let start = Instant::now();
+        let mut applied = Vec::new();
+        let result = (0..self.files.len()).try_for_each(|ind| stream_file(self, ind, &mut applied));
+
+        self.report.elapsed = if self.measure_time { Some(start.elapsed()) } else { None };
+        self.report.parsed = applied.len();
+
+        for (key, file, outcome) in applied {
+            match outcome {
+                Streamed::Set => {
+                    self.sources.insert(key.clone(), PathBuf::from(&self.files[file]));
+                    self.report.set.push(key);
+                },
+                Streamed::Unset => self.report.unset.push(key),
+                Streamed::Skipped => self.report.skipped.push(key),
+            }
+        }
+
+        if let Err(err) = &result {
+            self.report.errors.push(err.to_string());
+        }
+
+        result
+    }
+
+    /// Calls the callback from fn file_resolver the first time the loader is activated, see there.
+    fn resolve_file(&mut self) -> Result<(), EnvError> {
+        let resolver = match self.file_resolver.take() {
+            Some(resolver) => resolver,
+            None => return Ok(()),
+        };
+
+        let resolved = (resolver.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))();
+
+        match resolved {
+            Ok(path) => {
+                self.files = vec![path];
+                Ok(())
+            },
+            Err(err) => {
+                self.report = LoadReport { errors: vec![err.to_string()], ..LoadReport::default() };
+                Err(err)
+            },
+        }
+    }
+
+    /// Returns what happened during the last fn activate: the files loaded, which keys were set or skipped,
+    /// and any warnings or errors. Before the first fn activate the report is empty.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     for key in &loader.report().skipped {
+    ///         println!("{} was already set", key);
+    ///     }
+    /// }
+    /// ```
+    pub fn report(&self) -> &LoadReport {
+        &self.report
+    }
+
+    /// Returns the keys of the last fn activate whose value in the files was the same as the one already in the
+    /// environment, in file order. Those lines don't change anything where the variables are set beforehand,
+    /// so they are the ones worth checking when cleaning up a file which restates defaults.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     for key in loader.redundant_keys() {
+    ///         println!("{} has the same value as the environment", key);
+    ///     }
+    /// }
+    /// ```
+    pub fn redundant_keys(&self) -> &[String] {
+        &self.report.redundant
+    }
+
+    /// Returns the report of the last fn activate as JSON, for tools which want to read the outcome of a load.
+    /// Only available with the `serde_json` feature.
+    /// 
+    /// The document always has this shape, and fields will only ever be added to it:
+    /// 
+    /// ```text
+    /// {
+    ///   "version": 1,
+    ///   "success": true,
+    ///   "files": ["./.env_plus"],
+    ///   "counts": { "parsed": 3, "set": 2, "skipped": 1, "warnings": 0, "errors": 0 },
+    ///   "set_keys": ["SECRET", "TOKEN"],
+    ///   "skipped_keys": ["PATH"],
+    ///   "warnings": [],
+    ///   "errors": [],
+    ///   "requested_found": [],
+    ///   "requested_missing": [],
+    ///   "elapsed_micros": null,
+    ///   "cache_hit": false,
+    ///   "redundant_keys": [],
+    ///   "unset_keys": []
+    /// }
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn report_json(&self) -> Result<String, EnvError> {
+        let report = &self.report;
+
+        let json = serde_json::json!({
+            "version": 1,
+            "success": report.errors.is_empty(),
+            "files": report.files.iter().map(|file| file.to_string_lossy()).collect::<Vec<_>>(),
+            "counts": {
+                "parsed": report.parsed,
+                "set": report.set.len(),
+                "skipped": report.skipped.len(),
+                "warnings": report.warnings.len(),
+                "errors": report.errors.len(),
+            },
+            "set_keys": report.set,
+            "skipped_keys": report.skipped,
+            "warnings": report.warnings,
+            "errors": report.errors,
+            "requested_found": report.requested_found,
+            "requested_missing": report.requested_missing,
+            "elapsed_micros": report.elapsed.map(|elapsed| elapsed.as_micros() as u64),
+            "cache_hit": report.cache_hit,
+            "redundant_keys": report.redundant,
+            "unset_keys": report.unset,
+        });
+
+        serde_json::to_string_pretty(&json).map_err(|err| EnvError::Serialize { message: err.to_string() })
+    }
+
+    /// Parse the file into a tree by splitting every key on the separator. The parts of the key are
+    /// lowercased, so with `_` the keys `DB_HOST` and `DB_PORT` become `{"db": {"host": ..., "port": ...}}`.
+    /// Every value is a string. When a key would be both a value and the parent of other keys, like `DB`
+    /// next to `DB_HOST`, or two keys end up at the same place, an EnvError::NestingConflict with both keys
+    /// is returned. Nothing is set in the environment.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// DB_HOST=localhost
+    /// DB_PORT=5432
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let tree = EnvLoader::new()
+    ///     .parse_nested('_')
+    ///     .unwrap();
+    /// 
+    ///     assert_eq!(tree["db"]["port"], "5432");
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn parse_nested(&self, separator: char) -> Result<serde_json::Value, EnvError> {
+        use serde_json::{Map, Value};
+
+        let mut root = Map::new();
+        let mut owners: HashMap<String, String> = HashMap::new();
+
+        for (key, value) in self.parse_ordered()? {
+            let path: Vec<String> = key.split(separator).map(str::to_ascii_lowercase).collect();
+            let mut node = &mut root;
+
+            for depth in 0..path.len() {
+                let owner = owners.entry(path[..=depth].join(&separator.to_string())).or_insert_with(|| key.clone());
+                let conflict = EnvError::NestingConflict { key: key.clone(), other: owner.clone() };
+                let segment = path[depth].clone();
+
+                if depth + 1 == path.len() {
+                    if node.contains_key(&segment) { return Err(conflict) };
+
+                    node.insert(segment, Value::String(value));
+                    break;
+                }
+
+                node = match node.entry(segment).or_insert_with(|| Value::Object(Map::new())) {
+                    Value::Object(child) => child,
+                    _ => return Err(conflict),
+                };
+            }
+        }
+
+        Ok(Value::Object(root))
+    }
+
+    /// Returns which file the value of each key set by fn activate came from. Keys which were left alone
+    /// because they were already in the environment are not included. The keys of every call to fn activate
+    /// since the loader was created or fn reset_state was called are kept, and a key set again comes from
+    /// the file of the latest call.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new()
+    ///     .add_file(String::from("./.env_plus.local"));
+    /// 
+    ///     loader.activate().unwrap();
+    /// 
+    ///     for (key, file) in loader.sources() {
+    ///         println!("{} was set from {}", key, file.display());
+    ///     }
+    /// }
+    /// ```
+    pub fn sources(&self) -> &HashMap<String, PathBuf> {
+        &self.sources
+    }
+
+    /// Reads a variable from the environment as an integer, usually after fn activate has loaded it. Besides
+    /// plain numbers, it accepts the same forms as Rust integer literals: underscores between digits, like
+    /// `1_000_000`, and the prefixes `0x`, `0o` and `0b` for hexadecimal, octal and binary, like `0xFF`. A sign
+    /// goes before the prefix. A variable which is not set is an EnvError::MissingRequired, and one which is not
+    /// an integer, has an underscore at the start or end of its digits or two in a row, or a prefix without
+    /// digits, is an EnvError::InvalidValue.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// MAX_CONNECTIONS=1_000_000
+    /// MASK=0xFF
+    /// PERMS=0o755
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     assert_eq!(loader.get_int("MAX_CONNECTIONS").unwrap(), 1_000_000);
+    ///     assert_eq!(loader.get_int("MASK").unwrap(), 255);
+    ///     assert_eq!(loader.get_int("PERMS").unwrap(), 0o755);
+    /// }
+    /// ```
+    pub fn get_int(&self, key: &str) -> Result<i64, EnvError> {
+        let value = std::env::var(key).map_err(|_| EnvError::MissingRequired { key: key.to_string(), suggestion: None })?;
+
+        let value = match self.digit_grouping {
+            Some(separator) if value.contains(separator) => ungroup_digits(&value, separator),
+            _ => Ok(value),
+        };
+
+        value.and_then(|value| parse_int(&value)).map_err(|message| EnvError::InvalidValue { line: 0, key: key.to_string(), message })
+    }
+
+    /// Reads a variable from the environment as a list, usually after fn activate has loaded it. The value is
+    /// split on the separator and every item is trimmed, so `a, b` with `,` gives `a` and `b`. Empty items are
+    /// dropped unless fn keep_empty_list_items is on, and an empty variable is always an empty list. A variable
+    /// which is not set is an EnvError::MissingRequired.
+    /// 
+    /// # Examples
+    /// 
+    /// ```text
+    /// // .env_plus
+    /// 
+    /// ALLOWED_HOSTS=localhost, example.com,
+    /// ```
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     assert_eq!(loader.get_list("ALLOWED_HOSTS", ",").unwrap(), vec!["localhost", "example.com"]);
+    /// }
+    /// ```
+    pub fn get_list(&self, key: &str, separator: &str) -> Result<Vec<String>, EnvError> {
+        let value = std::env::var(key).map_err(|_| EnvError::MissingRequired { key: key.to_string(), suggestion: None })?;
+
+        if value.trim().is_empty() {
+            return Ok(Vec::new())
+        }
+
+        Ok(value
+            .split(separator)
+            .map(str::trim)
+            .filter(|item| self.keep_empty_items || !item.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Forgets what earlier calls to fn activate did, so fn sources and fn report are empty again. The
+    /// options and the variables which were set in the environment are not changed.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     let mut loader = EnvLoader::new();
+    ///     loader.activate().unwrap();
+    /// 
+    ///     loader.reset_state();
+    ///     assert!(loader.sources().is_empty());
+    /// }
+    /// ```
+    pub fn reset_state(&mut self) {
+        self.sources.clear();
+        self.report = LoadReport::default();
+        self.content_hash = None;
+    }
+
+    /// Loads the files with fn activate even if fn skip_unchanged would skip them.
+    pub fn force_reload(&mut self) -> Result<(), EnvError> {
+        self.content_hash = None;
+        self.activate()
+    }
+
+    /// Runs the given keys and values through everything fn activate does after parsing the file, such as
+    /// the key options, aliases, required keys, fn apply_only, fn check_env_size and the overwrite check, and sets
+    /// them. The file is not read.
+    /// Errors which point at a line use the position of the pair instead, starting from 1.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     EnvLoader::new()
+    ///     .uppercase_keys(true)
+    ///     .apply_pairs(vec![(String::from("secret"), String::from("YOUR_SECRET"))])
+    ///     .unwrap();
+    /// 
+    ///     let secret = std::env::var("SECRET").unwrap();
+    ///     assert_eq!(secret, String::from("YOUR_SECRET"));
+    /// }
+    /// ```
+    pub fn apply_pairs<I: IntoIterator<Item = (String, String)>>(self, pairs: I) -> Result<(), EnvError> {
+        let parsed = pairs
+            .into_iter()
+            .enumerate()
+            .map(|(ind, (key, value))| Entry { key: Cow::Owned(key), value: Cow::Owned(value), line: ind + 1, span: 0..0, file: 0, assignment: Assignment::Set })
+            .collect();
+
+        let mut entries = process_entries(&self, parsed, &mut Vec::new())?;
+        prepare_entries(&self, &mut entries, &env_lengths(), &mut LoadReport::default())?;
+        set_entries(&self, entries, &mut |_| {});
+
+        Ok(())
+    }
+
+    /// Activate the module the same way as fn activate, but panic if the file could not be loaded.
+    /// The panic message contains the path of the file and the cause of the error.
+    /// 
+    /// This is handy for small scripts which can't continue without their config.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// // main.rs 
+    /// use env_plus::EnvLoader;
+    /// 
+    /// fn main() {
+    ///     // Panics with "Failed to load ENV file './missing.env': Could not read ..."
+    ///     EnvLoader::new()
+    ///     .change_file(String::from("./missing.env"))
+    ///     .activate_or_panic();
+    /// }
+    /// ```
+    pub fn activate_or_panic(&mut self) {
+        if let Err(err) = self.activate() {
+            panic!("Failed to load ENV file '{}': {}", self.files.join("', '"), err)
+        }
+    }
+}
+
+
+/// Returns the entries of a map from fn EnvLoader::parse whose keys start with the prefix. If strip is
+/// true, the prefix is removed from the keys which are returned.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// // main.rs
+/// use env_plus::{with_prefix, EnvLoader};
+/// 
+/// fn main() {
+///     let vars = EnvLoader::new().parse().unwrap();
+///     let db = with_prefix(&vars, "DB_", true);
+/// 
+///     println!("Connecting to {}:{}", db["HOST"], db["PORT"]);
+/// }
+/// ```
+pub fn with_prefix(map: &HashMap<String, String>, prefix: &str, strip: bool) -> HashMap<String, String> {
+    map.iter()
+        .filter_map(|(key, value)| {
+            let rest = key.strip_prefix(prefix)?;
+            let key = if strip { rest } else { key.as_str() };
+
+            Some((key.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+
+/// The comment style or the delimiter. A single character is matched as a `char`, see fn change_comment_char.
+#[derive(Clone, Debug)]
+enum Marker {
+    Char(char),
+    Text(String),
+}
+
+
+impl Marker {
+    fn find(&self, text: &str) -> Option<usize> {
+        match self {
+            Marker::Char(marker) => text.find(*marker),
+            Marker::Text(marker) => text.find(marker.as_str()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Marker::Char(marker) => marker.len_utf8(),
+            Marker::Text(marker) => marker.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn starts(&self, text: &str) -> bool {
+        self.strip_prefix(text).is_some()
+    }
+
+    fn strip_prefix<'a>(&self, text: &'a str) -> Option<&'a str> {
+        match self {
+            Marker::Char(marker) => text.strip_prefix(*marker),
+            Marker::Text(marker) => text.strip_prefix(marker.as_str()),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Marker::Char(marker) => marker.to_string(),
+            Marker::Text(marker) => marker.clone(),
+        }
+    }
+
+    /// The text up to the first marker, or all of it when there is none.
+    fn before<'a>(&self, text: &'a str) -> &'a str {
+        match self.find(text) {
+            Some(pos) => &text[..pos],
+            None => text,
+        }
+    }
+}
+
+
+/// A parsed line. The key and value borrow from the file buffer until something changes them.
+#[derive(Clone)]
+struct Entry<'a> {
+    key: Cow<'a, str>,
+    value: Cow<'a, str>,
+    line: usize,
+    span: Range<usize>,
+    /// The position of the file the entry is from in EnvLoader::files.
+    file: usize,
+    assignment: Assignment,
+}
+
+
+impl Entry<'_> {
+    fn into_owned(self) -> Entry<'static> {
+        Entry {
+            key: Cow::Owned(self.key.into_owned()),
+            value: Cow::Owned(self.value.into_owned()),
+            line: self.line,
+            span: self.span,
+            file: self.file,
+            assignment: self.assignment,
+        }
+    }
+}
+
+
+/// Reads the file, dropping a leading byte order mark unless fn strict_utf8 is on, in which case the mark
+/// and invalid UTF-8 are both errors.
+fn read_file(path: &str, envs: &EnvLoader) -> Result<String, EnvError> {
+    check_permissions(path, envs)?;
+
+    let bytes = read_bytes(path, envs)?;
+
+    let mut file = match String::from_utf8(bytes) {
+        Ok(file) => file,
+        Err(err) if envs.strict_utf8 => {
+            return Err(EnvError::Encoding { path: path.to_string(), message: err.utf8_error().to_string() })
+        }
+        Err(err) => {
+            return Err(EnvError::Io {
+                path: path.to_string(),
+                source: std::io::Error::new(std::io::ErrorKind::InvalidData, err.utf8_error()),
+            })
+        }
+    };
+
+    if file.starts_with('\u{feff}') {
+        if envs.strict_utf8 {
+            return Err(EnvError::Encoding { path: path.to_string(), message: String::from("the file starts with a byte order mark") })
+        }
+
+        // Removed in place so a secret file is not copied before it is zeroized.
+        file.drain(..'\u{feff}'.len_utf8());
+    }
+
+    Ok(file)
+}
+
+
+/// Reads the whole file, the entry from fn change_archive_entry if the path is its archive, or the file from
+/// fn load_embedded_dir.
+fn read_bytes(path: &str, envs: &EnvLoader) -> Result<Vec<u8>, EnvError> {
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    if let Some((archive, entry)) = &envs.archive_entry {
+        if archive == path { return read_archive_entry(archive, entry) };
+    }
+
+    #[cfg(feature = "include_dir")]
+    if let Some(file) = envs.embedded.and_then(|dir| dir.get_file(path)) {
+        return Ok(file.contents().to_vec())
+    }
+
+    #[cfg(not(any(feature = "tar", feature = "zip", feature = "include_dir")))]
+    let _ = envs;
+
+    fs::read(path).map_err(|source| EnvError::Io { path: path.to_string(), source })
+}
+
+
+/// Extracts the bytes of a single entry, picking the archive format from the extension of the path.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn read_archive_entry(archive: &str, entry: &str) -> Result<Vec<u8>, EnvError> {
+    let file = fs::File::open(archive).map_err(|source| EnvError::Io { path: archive.to_string(), source })?;
+    let extension = std::path::Path::new(archive).extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+
+    let archive_error = |message: String| EnvError::Archive { path: archive.to_string(), message };
+    let missing = || EnvError::MissingArchiveEntry { archive: archive.to_string(), entry: entry.to_string() };
+
+    match extension.as_deref() {
+        #[cfg(feature = "zip")]
+        Some("zip") => {
+            let mut zip = zip::ZipArchive::new(file).map_err(|err| archive_error(err.to_string()))?;
+
+            let mut item = match zip.by_name(entry) {
+                Ok(item) => item,
+                Err(zip::result::ZipError::FileNotFound) => return Err(missing()),
+                Err(err) => return Err(archive_error(err.to_string())),
+            };
+
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut item, &mut bytes).map_err(|err| archive_error(err.to_string()))?;
+
+            Ok(bytes)
+        },
+        #[cfg(feature = "tar")]
+        Some("tar") => {
+            // Tools write entry names with and without a leading `./`, so both are compared without it.
+            let wanted: PathBuf = std::path::Path::new(entry).components().filter(|part| *part != std::path::Component::CurDir).collect();
+            let mut tar = tar::Archive::new(file);
+
+            for item in tar.entries().map_err(|err| archive_error(err.to_string()))? {
+                let mut item = item.map_err(|err| archive_error(err.to_string()))?;
+                let name: PathBuf = item
+                    .path()
+                    .map_err(|err| archive_error(err.to_string()))?
+                    .components()
+                    .filter(|part| *part != std::path::Component::CurDir)
+                    .collect();
+
+                if name == wanted {
+                    let mut bytes = Vec::new();
+                    std::io::Read::read_to_end(&mut item, &mut bytes).map_err(|err| archive_error(err.to_string()))?;
+
+                    return Ok(bytes)
+                }
+            }
+
+            Err(missing())
+        },
+        _ => Err(archive_error(String::from("only .zip and .tar archives can be read, each with its feature enabled"))),
+    }
+}
+
+
+/// Fails when the file can be read by its group or by other users while fn require_secure_permissions is on.
+fn check_permissions(path: &str, envs: &EnvLoader) -> Result<(), EnvError> {
+    #[cfg(feature = "include_dir")]
+    if envs.embedded.is_some_and(|dir| dir.contains(path)) { return Ok(()) };
+
+    #[cfg(unix)]
+    if envs.secure_permissions {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path)
+            .map_err(|source| EnvError::Io { path: path.to_string(), source })?
+            .permissions()
+            .mode();
+
+        if mode & 0o044 != 0 {
+            return Err(EnvError::InsecurePermissions { path: path.to_string(), mode })
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = (path, envs);
+
+    Ok(())
+}
+
+
+/// What fn activate_streaming did with a key.
+enum Streamed {
+    Set,
+    Unset,
+    Skipped,
+}
+
+
+/// Reads a file for fn activate_streaming with a single line buffer, which is zeroized afterwards like a whole
+/// file would be. Every key is added to applied with its file and what was done with it.
+fn stream_file(envs: &EnvLoader, file_ind: usize, applied: &mut Vec<(String, usize, Streamed)>) -> Result<(), EnvError> {
+    let path = &envs.files[file_ind];
+
+    check_permissions(path, envs)?;
+
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    if let Some((archive, entry)) = &envs.archive_entry {
+        if archive == path {
+            let mut buffer = String::new();
+            let result = stream_lines(envs, file_ind, std::io::Cursor::new(read_archive_entry(archive, entry)?), &mut buffer, applied);
+
+            #[cfg(feature = "zeroize")]
+            if !envs.secrets.is_empty() {
+                zeroize::Zeroize::zeroize(&mut buffer);
+            }
+
+            return result
+        }
+    }
+
+    #[cfg(feature = "include_dir")]
+    if let Some(file) = envs.embedded.and_then(|dir| dir.get_file(path)) {
+        return stream_lines(envs, file_ind, file.contents(), &mut String::new(), applied)
+    }
+
+    let file = fs::File::open(path).map_err(|source| EnvError::Io { path: path.to_string(), source })?;
+
+    #[allow(unused_mut)]
+    let mut buffer = String::new();
+    let result = stream_lines(envs, file_ind, std::io::BufReader::new(file), &mut buffer, applied);
+
+    #[cfg(feature = "zeroize")]
+    if !envs.secrets.is_empty() {
+        zeroize::Zeroize::zeroize(&mut buffer);
+    }
+
+    result
+}
+
+
+fn stream_lines<R: std::io::BufRead>(
+    envs: &EnvLoader,
+    file_ind: usize,
+    mut reader: R,
+    buffer: &mut String,
+    applied: &mut Vec<(String, usize, Streamed)>,
+) -> Result<(), EnvError> {
+    let path = &envs.files[file_ind];
+    let mut pending: Option<Entry<'static>> = None;
+    let mut count = 0;
+
+    for ind in 0.. {
+        buffer.clear();
+
+        let read = read_record(&mut reader, buffer, envs.cr_newlines).map_err(|source| match source.kind() {
+            std::io::ErrorKind::InvalidData if envs.strict_utf8 => EnvError::Encoding { path: path.to_string(), message: source.to_string() },
+            _ => EnvError::Io { path: path.to_string(), source },
+        })?;
+
+        if read == 0 { break };
+
+        let mut line = buffer.strip_suffix('\n').unwrap_or(buffer);
+        line = line.strip_suffix('\r').unwrap_or(line);
+
+        if ind == 0 {
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                if envs.strict_utf8 {
+                    return Err(EnvError::Encoding { path: path.to_string(), message: String::from("the file starts with a byte order mark") })
+                }
+
+                line = rest;
+            }
+
+            if skips_first_line(line, envs) { continue };
+        }
+
+        if envs.greedy_values {
+            if let (Some(main_line), Some(last)) = (strip_line(line, envs), pending.as_mut()) {
+                if !looks_like_key(main_line, envs) {
+                    let value = last.value.to_mut();
+                    value.push('\n');
+                    value.push_str(main_line.trim_end());
+                    continue;
+                }
+            }
+        }
+
+        if envs.limit.is_some_and(|limit| count >= limit) { break };
+
+        if let Some(mut entry) = recover_line(line, envs, ind)?.map(Entry::into_owned) {
+            entry.file = file_ind;
+            count += 1;
+
+            if let Some(done) = pending.take() {
+                apply_streamed(envs, done, applied)?;
+            }
+
+            if envs.greedy_values {
+                pending = Some(entry);
+            } else {
+                apply_streamed(envs, entry, applied)?;
+            }
+        }
+    }
+
+    match pending {
+        Some(done) => apply_streamed(envs, done, applied),
+        None => Ok(()),
+    }
+}
+
+
+/// Reads the next line into the buffer for fn activate_streaming, like `BufRead::read_line`, but also ending it
+/// at a lone `\r` when cr is true, see fn treat_cr_as_newline. The line ending is kept.
+fn read_record<R: std::io::BufRead>(reader: &mut R, buffer: &mut String, cr: bool) -> std::io::Result<usize> {
+    if !cr { return reader.read_line(buffer) };
+
+    let mut bytes = std::mem::take(buffer).into_bytes();
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() { break };
+
+        match available.iter().position(|byte| *byte == b'\n' || *byte == b'\r') {
+            Some(pos) => {
+                let ending = available[pos];
+                bytes.extend_from_slice(&available[..=pos]);
+                reader.consume(pos + 1);
+
+                if ending == b'\r' && reader.fill_buf()?.first() == Some(&b'\n') {
+                    bytes.push(b'\n');
+                    reader.consume(1);
+                }
+
+                break
+            },
+            None => {
+                let len = available.len();
+                bytes.extend_from_slice(available);
+                reader.consume(len);
+            },
+        }
+    }
+
+    let read = bytes.len();
+
+    *buffer = String::from_utf8(bytes).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.utf8_error()))?;
+
+    Ok(read)
+}
+
+
+/// Runs a single entry through the key and value options and sets it, see fn activate_streaming.
+fn apply_streamed(envs: &EnvLoader, mut entry: Entry<'static>, applied: &mut Vec<(String, usize, Streamed)>) -> Result<(), EnvError> {
+    entry.key = transform_key(entry.key, envs);
+    entry.value = transform_value(&entry.key, entry.value, envs);
+
+    #[cfg(feature = "serde")]
+    if envs.file_references && entry.value.starts_with('@') {
+        if let Some(value) = resolve_reference(&entry, envs)? {
+            entry.value = Cow::Owned(value);
+        }
+    }
+
+    let expanded = if envs.lists { expand_list(entry, envs)? } else { vec![entry] };
+
+    for mut entry in expanded {
+        if let Some(marker) = &envs.env_reference_marker {
+            // The earlier keys are already set, so both kinds of reference are found in the environment.
+            let name = entry.value.strip_prefix(marker.as_str()).filter(|name| !name.is_empty());
+            let value = name.map(|name| std::env::var(transform_key(Cow::Borrowed(name), envs).as_ref()).or_else(|_| std::env::var(name)));
+
+            match value {
+                Some(Ok(value)) => entry.value = Cow::Owned(value),
+                Some(Err(_)) => continue,
+                None => {},
+            }
+        }
+
+        if envs.match_key_case {
+            match_existing_case(std::slice::from_mut(&mut entry), &env_lengths());
+        }
+
+        let (key, file) = (entry.key.to_string(), entry.file);
+
+        let outcome = match set_entries(envs, vec![entry], &mut |_| {}) {
+            (set, _) if !set.is_empty() => Streamed::Set,
+            (_, unset) if !unset.is_empty() => Streamed::Unset,
+            _ => Streamed::Skipped,
+        };
+
+        applied.push((key, file, outcome));
+    }
+
+    Ok(())
+}
+
+
+/// Hashes the paths and contents of the files, see fn skip_unchanged.
+fn contents_hash(envs: &EnvLoader) -> Result<u64, EnvError> {
+    let mut hash = FNV_OFFSET;
+
+    for path in &envs.files {
+        let bytes = read_bytes(path, envs)?;
+
+        hash = fnv1a(hash, path.as_bytes());
+        hash = fnv1a(hash, &[0]);
+        hash = fnv1a(hash, &(bytes.len() as u64).to_le_bytes());
+        hash = fnv1a(hash, &bytes);
+    }
+
+    Ok(hash)
+}
+
+
+/// Reads and parses every file, one after another.
+fn load_file(envs: &EnvLoader) -> Result<Vec<Entry<'static>>, EnvError> {
+    load_file_with_progress(envs, &mut |_| {})
+}
+
+
+fn load_file_with_progress(envs: &EnvLoader, progress: &mut dyn FnMut(ProgressEvent)) -> Result<Vec<Entry<'static>>, EnvError> {
+    let mut entries = Vec::new();
+
+    for (ind, path) in envs.files.iter().enumerate() {
+        progress(ProgressEvent::FileStarted(PathBuf::from(path)));
+
+        #[allow(unused_mut)]
+        let mut file = read_file(path, envs)?;
+        let parsed = parse_source(&file, ind, envs).map(|parsed| parsed.into_iter().map(Entry::into_owned).collect::<Vec<_>>());
+
+        #[cfg(feature = "zeroize")]
+        if !envs.secrets.is_empty() {
+            zeroize::Zeroize::zeroize(&mut file);
+        }
+
+        let parsed = parsed?;
+        progress(ProgressEvent::FileFinished(PathBuf::from(path), parsed.len()));
+
+        entries.extend(parsed);
+    }
+
+    Ok(entries)
+}
+
+
+fn parse_source<'a>(file: &'a str, file_ind: usize, envs: &EnvLoader) -> Result<Vec<Entry<'a>>, EnvError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let records = records(file, envs);
+    let mut next = 0;
+
+    while next < records.len() {
+        let (ind, line) = (next, records[next]);
+        next += 1;
+
+        if ind == 0 && skips_first_line(line, envs) { continue };
+
+        if envs.greedy_values {
+            if let (Some(main_line), Some(last)) = (strip_line(line, envs), entries.last_mut()) {
+                if !looks_like_key(main_line, envs) {
+                    let value = last.value.to_mut();
+                    value.push('\n');
+                    value.push_str(main_line.trim_end());
+                    last.span.end = line.as_ptr() as usize - file.as_ptr() as usize + line.len();
+                    continue;
+                }
+            }
+        }
+
+        if envs.limit.is_some_and(|limit| entries.len() >= limit) { break };
+
+        let start = line.as_ptr() as usize - file.as_ptr() as usize;
+
+        let entry = if let Some(main_line) = continued_line(line, envs) {
+            let mut joined = main_line.to_string();
+            let mut last = line;
+
+            while continued_line(last, envs).is_some() && next < records.len() {
+                last = records[next];
+                next += 1;
+
+                joined.push_str(&envs.continuation_join);
+                joined.push_str(continued_line(last, envs).unwrap_or(last));
+            }
+
+            let end = last.as_ptr() as usize - file.as_ptr() as usize + last.len();
+            recover_line(&joined, envs, ind)?.map(|entry| Entry { span: start..end, ..entry.into_owned() })
+        } else {
+            recover_line(line, envs, ind)?.map(|entry| Entry { span: start..start + line.len(), ..entry })
+        };
+
+        if let Some(mut entry) = entry {
+            entry.file = file_ind;
+            entries.push(entry);
+        }
+    };
+
+    Ok(entries)
+}
+
+
+/// Reads a bracketed list value, see fn parse_bracketed_lists. Values which are not lists are returned as they are.
+fn expand_list<'a>(entry: Entry<'a>, envs: &EnvLoader) -> Result<Vec<Entry<'a>>, EnvError> {
+    let format = &envs.list_format;
+    let value = entry.value.trim();
+
+    let inner = match value.strip_prefix(format.open) {
+        Some(rest) => match rest.strip_suffix(format.close) {
+            Some(inner) => inner,
+            None if format.strict => {
+                return Err(EnvError::Malformed { line: entry.line, content: format!("{}={}", entry.key, entry.value) })
+            },
+            None => return Ok(vec![entry]),
+        },
+        None => return Ok(vec![entry]),
+    };
+
+    let items: Vec<String> = if inner.trim().is_empty() {
+        Vec::new()
+    } else {
+        inner.split(format.separator).map(|item| item.trim().to_string()).collect()
+    };
+
+    if !format.indexed {
+        return Ok(vec![Entry { value: Cow::Owned(items.join(&format.separator.to_string())), ..entry }])
+    }
+
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .map(|(ind, item)| Entry { key: Cow::Owned(format!("{}_{}", entry.key, ind)), value: Cow::Owned(item), ..entry.clone() })
+        .collect())
+}
+
+
+/// Removes the grouping character from a decimal number, after checking that it splits the digits into
+/// groups of three, see fn digit_grouping.
+fn ungroup_digits(value: &str, separator: char) -> Result<String, String> {
+    let trimmed = value.trim();
+
+    let (sign, rest) = match trimmed.strip_prefix(['-', '+']) {
+        Some(rest) => (&trimmed[..1], rest),
+        None => ("", trimmed),
+    };
+
+    let groups: Vec<&str> = rest.split(separator).collect();
+    let digits = |group: &str| group.chars().all(|c| c.is_ascii_digit());
+
+    let first = groups[0];
+    let grouped = (1..=3).contains(&first.len()) && digits(first) && groups[1..].iter().all(|group| group.len() == 3 && digits(group));
+
+    if !grouped {
+        return Err(format!("'{}' is not grouped in threes by '{}'", value, separator))
+    }
+
+    Ok(format!("{}{}", sign, groups.concat()))
+}
+
+
+/// Parses an integer written like a Rust literal, see fn get_int.
+fn parse_int(value: &str) -> Result<i64, String> {
+    let trimmed = value.trim();
+
+    let (sign, rest) = match trimmed.strip_prefix(['-', '+']) {
+        Some(rest) => (&trimmed[..1], rest),
+        None => ("", trimmed),
+    };
+
+    let (radix, digits) = match rest.get(..2).map(str::to_ascii_lowercase).as_deref() {
+        Some("0x") => (16, &rest[2..]),
+        Some("0o") => (8, &rest[2..]),
+        Some("0b") => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+
+    if digits.is_empty() {
+        return Err(format!("'{}' has no digits", value))
+    }
+
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return Err(format!("'{}' may only have single underscores between digits", value))
+    }
+
+    if !digits.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("'{}' is not an integer", value))
+    }
+
+    i64::from_str_radix(&format!("{}{}", sign, digits.replace('_', "")), radix)
+        .map_err(|err| format!("'{}' is not a base {} integer: {}", value, radix, err))
+}
+
+
+/// Loads the field an `@json:` or `@toml:` value points to, see fn resolve_file_references. Other values
+/// give None.
+#[cfg(feature = "serde")]
+fn resolve_reference(entry: &Entry, envs: &EnvLoader) -> Result<Option<String>, EnvError> {
+    let (format, reference) = match (entry.value.strip_prefix("@json:"), entry.value.strip_prefix("@toml:")) {
+        (Some(reference), _) => ("json", reference),
+        (_, Some(reference)) => ("toml", reference),
+        _ => return Ok(None),
+    };
+
+    let error = |message: String| EnvError::Reference { line: entry.line, key: entry.key.to_string(), message };
+
+    let (path, field) = reference
+        .rsplit_once(':')
+        .ok_or_else(|| error(format!("'{}' should be a file and a field separated by ':'", reference)))?;
+
+    let base = envs.files.get(entry.file).and_then(|file| std::path::Path::new(file).parent());
+    let path = base.map(|base| base.join(path)).unwrap_or_else(|| PathBuf::from(path));
+
+    let unreadable = |err: &dyn std::fmt::Display| error(format!("'{}': {}", path.display(), err));
+    let missing = || error(format!("'{}' has no field '{}'", path.display(), field));
+
+    let source = fs::read_to_string(&path).map_err(|err| unreadable(&err))?;
+
+    if format == "json" {
+        let mut value: serde_json::Value = serde_json::from_str(&source).map_err(|err| unreadable(&err))?;
+
+        for part in field.split('.') {
+            value = match value {
+                serde_json::Value::Object(mut map) => map.remove(part).ok_or_else(missing)?,
+                serde_json::Value::Array(mut items) => match part.parse::<usize>() {
+                    Ok(ind) if ind < items.len() => items.swap_remove(ind),
+                    _ => return Err(missing()),
+                },
+                _ => return Err(missing()),
+            };
+        }
+
+        return Ok(Some(match value {
+            serde_json::Value::String(text) => text,
+            other => other.to_string(),
+        }))
+    }
+
+    let mut value = toml::Value::Table(source.parse::<toml::Table>().map_err(|err| unreadable(&err))?);
+
+    for part in field.split('.') {
+        value = match value {
+            toml::Value::Table(mut table) => table.remove(part).ok_or_else(missing)?,
+            toml::Value::Array(mut items) => match part.parse::<usize>() {
+                Ok(ind) if ind < items.len() => items.swap_remove(ind),
+                _ => return Err(missing()),
+            },
+            _ => return Err(missing()),
+        };
+    }
+
+    Ok(Some(match value {
+        toml::Value::String(text) => text,
+        other => other.to_string(),
+    }))
+}
+
+
+/// Runs fn interpolate and fn simple_interpolation over the merged entries of every file, in the order the
+/// keys first appear. A reference sees the value the key is loaded with, whichever file it ends up coming from.
+fn interpolate_entries(envs: &EnvLoader, entries: &mut [Entry]) -> Result<(), EnvError> {
+    let mut earlier: HashMap<String, String> = HashMap::new();
+
+    for entry in entries {
+        if envs.interpolate && entry.value.contains("${") {
+            entry.value = Cow::Owned(interpolate(entry, &earlier, envs)?);
+        }
+
+        if envs.simple_interpolation && entry.value.contains('$') {
+            entry.value = Cow::Owned(substitute_simple(&entry.value, &earlier, envs));
+        }
+
+        earlier.insert(entry.key.to_string(), entry.value.to_string());
+    }
+
+    Ok(())
+}
+
+
+/// Replaces every value which is the marker followed by a name with the value of an earlier key or of the
+/// variable from the environment, see fn env_reference_marker. Entries whose name is not found are dropped.
+fn resolve_env_references<'a>(envs: &EnvLoader, entries: Vec<Entry<'a>>, marker: &str) -> Vec<Entry<'a>> {
+    let mut earlier: HashMap<String, String> = HashMap::new();
+    let mut resolved = Vec::with_capacity(entries.len());
+
+    for mut entry in entries {
+        if let Some(name) = entry.value.strip_prefix(marker).filter(|name| !name.is_empty()) {
+            match lookup(&earlier, name, envs).cloned().or_else(|| env_var(envs, name)) {
+                Some(value) => entry.value = Cow::Owned(value),
+                None => continue,
+            }
+        }
+
+        earlier.insert(entry.key.to_string(), entry.value.to_string());
+        resolved.push(entry);
+    }
+
+    resolved
+}
+
+
+/// Reads a variable from the environment the entries are loaded over: the start given to fn apply_to, or
+/// the process environment.
+fn env_var(envs: &EnvLoader, name: &str) -> Option<String> {
+    match &envs.context {
+        Some(context) => context.get(name).cloned(),
+        None => std::env::var(name).ok(),
+    }
+}
+
+
+/// Whether a variable is in the environment the entries are loaded over, see env_var.
+fn env_var_exists(envs: &EnvLoader, name: &str) -> bool {
+    match &envs.context {
+        Some(context) => context.contains_key(name),
+        None => std::env::var_os(name).is_some(),
+    }
+}
+
+
+/// Finds an earlier key by the name it has in the file, which the merged entries only have after the key options.
+fn lookup<'m>(earlier: &'m HashMap<String, String>, name: &str, envs: &EnvLoader) -> Option<&'m String> {
+    earlier.get(transform_key(Cow::Borrowed(name), envs).as_ref())
+}
+
+
+/// Replaces every `${NAME}` in the value with an earlier key or a variable from the environment, see
+/// fn interpolate. The length is checked as the value grows, so a value over the limit is never built.
+fn interpolate(entry: &Entry, earlier: &HashMap<String, String>, envs: &EnvLoader) -> Result<String, EnvError> {
+    let mut result = String::with_capacity(entry.value.len());
+    let mut rest = entry.value.as_ref();
+
+    let push = |result: &mut String, text: &str| match envs.max_expanded_length {
+        Some(limit) if result.len() + text.len() > limit => Err(EnvError::ExpansionTooLong {
+            line: entry.line,
+            key: entry.key.to_string(),
+            limit,
+        }),
+        _ => {
+            result.push_str(text);
+            Ok(())
+        },
+    };
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        push(&mut result, &rest[..start])?;
+
+        let name = &rest[start + 2..end];
+        let value = match lookup(earlier, name, envs) {
+            Some(value) => Some(Cow::Borrowed(value.as_str())),
+            None => env_var(envs, name).map(Cow::Owned),
+        };
+
+        let unresolved = match envs.strict_interpolation {
+            StrictInterpolation::Off => false,
+            StrictInterpolation::Undefined => value.is_none(),
+            StrictInterpolation::UndefinedOrEmpty => value.as_deref().is_none_or(str::is_empty),
+        };
+
+        if unresolved {
+            return Err(EnvError::UnresolvedReference { line: entry.line, key: entry.key.to_string(), name: name.to_string() })
+        }
+
+        push(&mut result, value.as_deref().unwrap_or_default())?;
+
+        rest = &rest[end + 1..];
+    }
+
+    push(&mut result, rest)?;
+    Ok(result)
+}
+
+
+/// Replaces `$NAME` at the start of the value or after a `/` with the value of an earlier key, see
+/// fn simple_interpolation.
+fn substitute_simple(value: &str, earlier: &HashMap<String, String>, envs: &EnvLoader) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(pos) = rest.find('$') {
+        let triggers = pos == 0 && result.is_empty() || rest[..pos].ends_with('/');
+        result.push_str(&rest[..pos]);
+
+        let after = &rest[pos + 1..];
+        let name_len = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+
+        match lookup(earlier, &after[..name_len], envs) {
+            Some(found) if triggers && name_len > 0 => result.push_str(found),
+            _ => {
+                result.push('$');
+                result.push_str(&after[..name_len]);
+            },
+        }
+
+        rest = &after[name_len..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+
+/// Whether the delimiter comes before any whitespace in the line, see fn greedy_values.
+fn looks_like_key(line: &str, envs: &EnvLoader) -> bool {
+    let line = line.trim_start();
+
+    match find_delimiter(line, envs) {
+        Some((delimiter, _, _)) => !line[..delimiter].contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+
+/// Parses a line and hands any error to the callback from fn on_error, if one is set.
+fn recover_line<'a>(line: &'a str, envs: &EnvLoader, ind: usize) -> Result<Option<Entry<'a>>, EnvError> {
+    let err = match load_line(line, envs, ind) {
+        Ok(entry) => return Ok(entry),
+        Err(err) => err,
+    };
+
+    let handler = match &envs.on_error {
+        Some(handler) => handler,
+        None => return Err(err),
+    };
+
+    let recovery = (handler.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))(ind + 1, line, &err);
+
+    match recovery {
+        Recovery::Skip => Ok(None),
+        Recovery::Abort => Err(err),
+        Recovery::Replace(replacement) => Ok(load_line(&replacement, envs, ind)?.map(Entry::into_owned)),
+    }
+}
+
+
+/// Splits the file into the records which are parsed one by one.
+fn records<'a>(file: &'a str, envs: &EnvLoader) -> Vec<&'a str> {
+    match &envs.record_separator {
+        Some(separator) => file
+            .split(separator.as_str())
+            .map(|record| record.trim_matches(|c| c == '\r' || c == '\n'))
+            .collect(),
+        None => split_lines(file, envs),
+    }
+}
+
+
+/// Whether the first line of a file is left out, see fn skip_first_line.
+fn skips_first_line(line: &str, envs: &EnvLoader) -> bool {
+    envs.skip_first_line || line.starts_with("#!")
+}
+
+
+/// Splits the file on `\n` and `\r\n`, and on a lone `\r` as well with fn treat_cr_as_newline.
+fn split_lines<'a>(file: &'a str, envs: &EnvLoader) -> Vec<&'a str> {
+    if !envs.cr_newlines { return file.lines().collect() };
+
+    let mut lines = Vec::new();
+    let mut rest = file;
+
+    while let Some(pos) = rest.find(['\r', '\n']) {
+        lines.push(&rest[..pos]);
+
+        let len = if rest[pos..].starts_with("\r\n") { 2 } else { 1 };
+        rest = &rest[pos + len..];
+    }
+
+    if !rest.is_empty() { lines.push(rest) };
+
+    lines
+}
+
+
+fn load_entries(envs: &EnvLoader) -> Result<Vec<Entry<'static>>, EnvError> {
+    process_entries(envs, load_file(envs)?, &mut Vec::new())
+}
+
+
+/// Runs the parsed entries through the key transforms, drops repeated keys and checks aliases and required keys.
+/// A repeated key in the same file replaces the earlier value only when overwriting is on, the same way setting
+/// them one after another would. A key from a later file always replaces the value from an earlier one.
+/// 
+/// Keys which only collide because of fn normalize_key_separators are added to the warnings, as are
+/// conflicting values from different files with ConflictPolicy::Warn.
+fn process_entries<'a>(envs: &EnvLoader, parsed: Vec<Entry<'a>>, warnings: &mut Vec<String>) -> Result<Vec<Entry<'a>>, EnvError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut positions: HashMap<Cow<'a, str>, usize> = HashMap::new();
+    let mut spellings: HashMap<String, (String, usize)> = HashMap::new();
+
+    let modified: Vec<Option<SystemTime>> = match envs.overwrite_policy {
+        OverwritePolicy::FileOrder => Vec::new(),
+        OverwritePolicy::NewerFileWins => envs.files
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            .collect(),
+    };
+
+    for mut entry in parsed {
+        let original = if envs.key_separators.is_empty() { None } else { Some(entry.key.to_string()) };
+
+        entry.key = transform_key(entry.key, envs);
+        entry.value = transform_value(&entry.key, entry.value, envs);
+
+        if let Some(original) = original {
+            match spellings.get(entry.key.as_ref()) {
+                Some((first, line)) if *first != original => warnings.push(format!(
+                    "'{}' on line {} and '{}' on line {} are both loaded as '{}'.",
+                    first, line, original, entry.line, entry.key,
+                )),
+                Some(_) => {},
+                None => { spellings.insert(entry.key.to_string(), (original, entry.line)); },
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        if envs.file_references && entry.value.starts_with('@') {
+            if let Some(value) = resolve_reference(&entry, envs)? {
+                entry.value = Cow::Owned(value);
+            }
+        }
+
+        let expanded = if envs.lists { expand_list(entry, envs)? } else { vec![entry] };
+
+        for entry in expanded {
+            match positions.get(entry.key.as_ref()) {
+                Some(&pos) => {
+                    match entry.assignment {
+                        Assignment::Set => {},
+                        Assignment::SetIfUnset => continue,
+                        Assignment::Append => {
+                            let earlier = &mut entries[pos];
+                            earlier.value = Cow::Owned(format!("{}{}{}", earlier.value, envs.append_separator, entry.value));
+                            continue
+                        },
+                    }
+
+                    let existing = entries[pos].file;
+
+                    if existing != entry.file && entries[pos].value != entry.value && envs.conflict_policy != ConflictPolicy::Ignore {
+                        let file = |ind: usize| envs.files.get(ind).cloned().unwrap_or_default();
+                        #[cfg(feature = "zeroize")]
+                        let secret = envs.secrets.iter().any(|secret| *secret == entry.key);
+                        #[cfg(not(feature = "zeroize"))]
+                        let secret = false;
+                        // Secrets are redacted, the message ends up in warnings and logs.
+                        let value = |value: &str| if secret { String::from("***") } else { value.to_string() };
+                        let err = EnvError::Conflict {
+                            key: entry.key.to_string(),
+                            first_file: file(existing),
+                            first_value: value(&entries[pos].value),
+                            second_file: file(entry.file),
+                            second_value: value(&entry.value),
+                        };
+
+                        match envs.conflict_policy {
+                            ConflictPolicy::Error => return Err(err),
+                            _ => warnings.push(err.to_string()),
+                        }
+                    }
+
+                    let replace = if existing == entry.file {
+                        envs.overwrite
+                    } else {
+                        match (modified.get(existing).copied().flatten(), modified.get(entry.file).copied().flatten()) {
+                            (Some(old), Some(new)) => new >= old,
+                            _ => true,
+                        }
+                    };
+
+                    if replace { entries[pos] = entry }
+                },
+                None => {
+                    positions.insert(entry.key.clone(), entries.len());
+                    entries.push(entry);
+                },
+            }
+        }
+    }
+
+    if let Some(marker) = &envs.env_reference_marker {
+        entries = resolve_env_references(envs, entries, marker);
+    }
+
+    if envs.interpolate || envs.simple_interpolation {
+        interpolate_entries(envs, &mut entries)?;
+    }
+
+    // The values of a Profile are not from any of the files, so their position is past the last one.
+    for (key, value) in &envs.overrides {
+        match entries.iter().position(|entry| entry.key == key.as_str()) {
+            Some(pos) => entries[pos].value = Cow::Owned(value.clone()),
+            None => entries.push(Entry {
+                key: Cow::Owned(key.clone()),
+                value: Cow::Owned(value.clone()),
+                line: 0,
+                span: 0..0,
+                file: envs.files.len(),
+                assignment: Assignment::Set,
+            }),
+        }
+    }
+
+    for (alias, canonical) in &envs.aliases {
+        let alias_pos = entries.iter().position(|entry| &entry.key == alias);
+        let canonical_pos = entries.iter().position(|entry| &entry.key == canonical);
+
+        match (alias_pos, canonical_pos) {
+            (Some(a), Some(c)) => {
+                if entries[a].value != entries[c].value {
+                    return Err(EnvError::AliasConflict { alias: alias.clone(), canonical: canonical.clone() })
+                }
+            },
+            (Some(pos), None) | (None, Some(pos)) => {
+                let other = if alias_pos.is_some() { canonical } else { alias };
+                let entry = Entry { key: Cow::Owned(other.clone()), ..entries[pos].clone() };
+                entries.insert(pos + 1, entry);
+            },
+            (None, None) => {},
+        }
+    }
+
+    check_required(envs, &entries)?;
+
+    Ok(entries)
+}
+
+
+fn check_required(envs: &EnvLoader, entries: &[Entry]) -> Result<(), EnvError> {
+    for key in &envs.required {
+        if entries.iter().any(|entry| &entry.key == key) || env_var_exists(envs, key) { continue };
+
+        let suggestion = entries
+            .iter()
+            .map(|entry| (levenshtein(key, &entry.key), &entry.key))
+            .filter(|(distance, _)| *distance <= envs.typo_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.to_string());
+
+        return Err(EnvError::MissingRequired { key: key.clone(), suggestion })
+    }
+
+    Ok(())
+}
+
+
+/// The number of single character insertions, deletions and substitutions needed to turn one string into the other.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            let cost = if a_char == *b_char { 0 } else { 1 };
+
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + cost);
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+
+/// The line without its comment and the `\` at its end, when it is an entry line which is continued on the
+/// next one, see fn line_continuation. A `\` in a comment doesn't continue the line.
+fn continued_line<'a>(line: &'a str, envs: &EnvLoader) -> Option<&'a str> {
+    if !envs.continuation { return None };
+
+    strip_line(line, envs)?.strip_suffix('\\')
+}
+
+
+/// Returns the part of the line before any comment, or None if there is nothing to load on it.
+fn strip_line<'a>(line: &'a str, envs: &EnvLoader) -> Option<&'a str> {
+    let comment = &envs.comment;
+
+    if comment.starts(line.trim()) || line.trim() == "" { return None };
+
+    Some(comment.before(line))
+}
+
+
+fn load_line<'a>(line: &'a str, envs: &EnvLoader, ind: usize) -> Result<Option<Entry<'a>>, EnvError> {
+    let main_line = match strip_line(line, envs) {
+        Some(main_line) => main_line,
+        None => return Ok(None),
+    };
+
+    if is_separator_line(main_line, envs) { return Ok(None) };
+
+    let (delimiter, assignment) = match find_delimiter(main_line, envs) {
+        Some((_, delimiter, assignment)) => (delimiter, assignment),
+        None => (&envs.value_delimiter, Assignment::Set),
+    };
+
+    let split = if envs.shell_compat { split_shell(line, main_line, delimiter) } else { split_default(line, main_line, delimiter) };
+
+    let (mut key, mut value) = match split {
+        Some(split) => split,
+        None => return Err(EnvError::Malformed { line: ind + 1, content: line.to_string() }),
+    };
+
+    if let Some(token) = &envs.key_token {
+        if let Some(rest) = key.trim_start().strip_prefix(token.as_str()) {
+            key = rest.trim_start();
+        }
+    }
+
+    if envs.trim {
+        key = key.trim();
+    }
+
+    if key.is_empty() {
+        return Err(EnvError::Malformed { line: ind + 1, content: line.to_string() })
+    }
+
+    if let Some(limit) = envs.max_key_length {
+        if key.len() > limit {
+            return Err(EnvError::KeyTooLong { line: ind + 1, length: key.len(), limit })
+        }
+    }
+
+    if envs.collapse_delimiters && !delimiter.is_empty() {
+        while let Some(rest) = delimiter.strip_prefix(value) {
+            value = rest;
+        }
+    }
+
+    let quoted = envs.quotes && value.trim_start().starts_with(['"', '\'']);
+
+    let mut value = if envs.quotes {
+        unquote(value, envs, line, ind)?
+    } else {
+        // fn strip_line already found the comment, so the value ends where main_line does and isn't scanned again.
+        let start = value.as_ptr() as usize - line.as_ptr() as usize;
+        &value[..main_line.len().saturating_sub(start).min(value.len())]
+    };
+
+    if let Some(separator) = envs.trailing_separator.filter(|_| !quoted) {
+        if let Some(rest) = value.trim_end().strip_suffix(separator) {
+            value = rest.trim_end();
+        }
+    }
+
+    if envs.trim && !quoted {
+        value = value.trim();
+    }
+
+    Ok(Some(Entry { key: Cow::Borrowed(key), value: Cow::Borrowed(value), line: ind + 1, span: 0..0, file: 0, assignment }))
+}
+
+
+/// Compares two lists of keys and values, returning the changes from old to new.
+fn diff_pairs(old: &[(String, String)], new: &[(String, String)]) -> Vec<EnvChange> {
+    let new_values: HashMap<&str, &str> = new.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+    let old_keys: HashSet<&str> = old.iter().map(|(key, _)| key.as_str()).collect();
+
+    let mut changes = Vec::new();
+
+    for (key, value) in old {
+        match new_values.get(key.as_str()) {
+            None => changes.push(EnvChange::Removed { key: key.clone(), value: value.clone() }),
+            Some(&new_value) if new_value != value => {
+                changes.push(EnvChange::Changed { key: key.clone(), old: value.clone(), new: new_value.to_string() })
+            },
+            Some(_) => {},
+        }
+    }
+
+    for (key, value) in new {
+        if !old_keys.contains(key.as_str()) {
+            changes.push(EnvChange::Added { key: key.clone(), value: value.clone() });
+        }
+    }
+
+    changes
+}
+
+
+/// Finds the delimiter, one from fn delimiters or an operator from fn assignment_operators which starts first in the text, preferring the
+/// longest one when several start at the same place.
+fn find_delimiter<'e>(text: &str, envs: &'e EnvLoader) -> Option<(usize, &'e Marker, Assignment)> {
+    envs.operators
+        .iter()
+        .map(|(operator, assignment)| (operator, *assignment))
+        .chain(envs.delimiters.iter().map(|delimiter| (delimiter, Assignment::Set)))
+        .chain(std::iter::once((&envs.value_delimiter, Assignment::Set)))
+        .filter_map(|(operator, assignment)| Some((operator.find(text)?, operator, assignment)))
+        .min_by_key(|(pos, operator, _)| (*pos, std::cmp::Reverse(operator.len())))
+}
+
+
+fn is_separator_line(line: &str, envs: &EnvLoader) -> bool {
+    let line = line.trim();
+    let mut chars = line.chars();
+
+    match chars.next() {
+        Some(first) => envs.separator_chars.contains(&first) && line.len() > first.len_utf8() && chars.all(|c| c == first),
+        None => false,
+    }
+}
+
+
+/// Splits the line on the first delimiter. The value still has its comment, which is removed later.
+fn split_default<'a>(line: &'a str, main_line: &str, delimiter: &Marker) -> Option<(&'a str, &'a str)> {
+    let pos = delimiter.find(main_line)?;
+
+    Some((&line[..pos], &line[pos + delimiter.len()..]))
+}
+
+
+/// Splits a line in the formats accepted by fn shell_compat.
+fn split_shell<'a>(line: &'a str, main_line: &str, delimiter: &Marker) -> Option<(&'a str, &'a str)> {
+    let mut rest = main_line.trim_start();
+
+    if let Some(after) = rest.strip_prefix("export") {
+        if after.starts_with(char::is_whitespace) {
+            rest = after.trim_start();
+        }
+    }
+
+    let start = main_line.len() - rest.len();
+    let delimiter_pos = delimiter.find(rest);
+    let whitespace_pos = rest.find(char::is_whitespace);
+
+    let (key_end, value_start) = match (delimiter_pos, whitespace_pos) {
+        (Some(d), None) => (d, d + delimiter.len()),
+        (Some(d), Some(w)) if d < w || rest[w..d].trim().is_empty() => (w.min(d), d + delimiter.len()),
+        (_, Some(w)) => (w, w),
+        (None, None) => return None,
+    };
+
+    Some((&line[start..start + key_end], line[start + value_start..].trim_start()))
+}
+
+
+/// Takes the value out of its quotes, or strips the comment from it if it isn't quoted.
+fn unquote<'a>(value: &'a str, envs: &EnvLoader, line: &str, ind: usize) -> Result<&'a str, EnvError> {
+    let comment = &envs.comment;
+    let trimmed = value.trim_start();
+
+    let quote = match trimmed.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => quote,
+        _ => return Ok(comment.before(value)),
+    };
+
+    let inner = &trimmed[1..];
+
+    match inner.find(quote) {
+        Some(end) => {
+            let after = inner[end + 1..].trim_start();
+
+            if !after.is_empty() && !comment.starts(after) {
+                return Err(EnvError::Malformed { line: ind + 1, content: line.to_string() })
+            }
+
+            Ok(&inner[..end])
+        },
+        None => match envs.unterminated_quote {
+            UnterminatedQuote::Error => Err(EnvError::UnterminatedQuote { line: ind + 1, content: line.to_string() }),
+            UnterminatedQuote::Literal => Ok(comment.before(value)),
+            UnterminatedQuote::ToEndOfLine => Ok(inner),
+        },
+    }
+}
 
 
-    let env_exists = std::env::var(key);
+fn transform_key<'a>(key: Cow<'a, str>, envs: &EnvLoader) -> Cow<'a, str> {
+    let key = if key.contains(envs.key_separators.as_slice()) {
+        Cow::Owned(key.replace(envs.key_separators.as_slice(), "_"))
+    } else {
+        key
+    };
+
+    let key = match (&envs.strip_prefix, key) {
+        (Some(prefix), Cow::Borrowed(key)) => Cow::Borrowed(key.strip_prefix(prefix.as_str()).unwrap_or(key)),
+        (Some(prefix), Cow::Owned(key)) => match key.strip_prefix(prefix.as_str()) {
+            Some(rest) => Cow::Owned(rest.to_string()),
+            None => Cow::Owned(key),
+        },
+        (None, key) => key,
+    };
+
+    let key = change_case(key, envs);
+
+    let key = match &envs.add_prefix {
+        Some(prefix) => Cow::Owned(format!("{}{}", prefix, key)),
+        None => key,
+    };
+
+    match &envs.add_suffix {
+        Some(suffix) => Cow::Owned(format!("{}{}", key, suffix)),
+        None => key,
+    }
+}
+
 
-    if env_exists.is_err() {
-        std::env::set_var(key, value)
+fn change_case<'a>(key: Cow<'a, str>, envs: &EnvLoader) -> Cow<'a, str> {
+    match (envs.key_case, envs.case_folding) {
+        (None, _) => key,
+        (Some(KeyCase::Upper), CaseFolding::Ascii) => Cow::Owned(key.to_ascii_uppercase()),
+        (Some(KeyCase::Lower), CaseFolding::Ascii) => Cow::Owned(key.to_ascii_lowercase()),
+        (Some(KeyCase::Upper), CaseFolding::Unicode) => Cow::Owned(key.to_uppercase()),
+        (Some(KeyCase::Lower), CaseFolding::Unicode) => Cow::Owned(key.to_lowercase()),
+    }
+}
+
+
+fn transform_value<'a>(key: &str, value: Cow<'a, str>, envs: &EnvLoader) -> Cow<'a, str> {
+    let value = if envs.strip_invisible && value.contains(INVISIBLE_CHARS) {
+        Cow::Owned(value.replace(INVISIBLE_CHARS, ""))
     } else {
-        if !overwrite { return }
+        value
+    };
+
+    let value = if envs.expand_paths && looks_like_path(&value) { Cow::Owned(expand_path(&value, envs)) } else { value };
+
+    if envs.path_lists.iter().any(|path_list| path_list == key) {
+        return Cow::Owned(convert_path_list(&value, cfg!(windows)))
+    }
+
+    value
+}
+
+
+/// Joins the paths of a list with `;` for Windows or with `:` otherwise, see fn normalize_path_lists. A colon
+/// after a single drive letter, like in `C:\bin`, belongs to the path and is kept.
+fn convert_path_list(value: &str, windows: bool) -> String {
+    if !windows { return value.replace(';', ":") };
+
+    let mut result = String::with_capacity(value.len());
+    let mut start = 0;
+
+    for (ind, c) in value.char_indices() {
+        let drive = c == ':'
+            && ind - start == 1
+            && value[start..ind].chars().all(|letter| letter.is_ascii_alphabetic())
+            && value[ind + 1..].starts_with(['\\', '/']);
+
+        if c == ';' || (c == ':' && !drive) {
+            result.push(';');
+            start = ind + 1;
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+
+fn looks_like_path(value: &str) -> bool {
+    if value.chars().any(char::is_whitespace) || value.contains("://") { return false };
+
+    value == "~" || value.starts_with("~/") || value.contains('/')
+}
+
+
+fn expand_path(value: &str, envs: &EnvLoader) -> String {
+    let mut rest = value;
+    let mut expanded = String::new();
+
+    if rest == "~" || rest.starts_with("~/") {
+        if let Some(home) = env_var(envs, "HOME").or_else(|| env_var(envs, "USERPROFILE")) {
+            expanded.push_str(&home);
+            rest = &rest[1..];
+        }
+    }
+
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        let (name, after, braced) = match rest.strip_prefix('{') {
+            Some(inner) => match inner.find('}') {
+                Some(end) => (&inner[..end], &inner[end + 1..], true),
+                None => ("", rest, false),
+            },
+            None => {
+                let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..], false)
+            },
+        };
+
+        match env_var(envs, name) {
+            Some(var) if !name.is_empty() => expanded.push_str(&var),
+            _ if braced => expanded.push_str(&format!("${{{}}}", name)),
+            _ => {
+                expanded.push('$');
+                expanded.push_str(name);
+            },
+        }
+
+        rest = after;
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+
+/// The code points removed by fn strip_invisible_chars.
+const INVISIBLE_CHARS: &[char] = &['\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}', '\u{feff}'];
+
+
+const DEFAULT_ENV_SIZE_LIMIT: usize = if cfg!(windows) { 32_767 } else { 2 * 1024 * 1024 };
+
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+
+/// The 64 bit FNV-1a hash, used because its output is the same in every build, unlike the std hashers.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
+
+
+/// Sets the entries in the process environment and returns the keys which were set, with the file each is from,
+/// and the keys which were removed, see fn unset_value_token. Progress gets a ProgressEvent::KeySet as soon as
+/// each key is set.
+fn set_entries(envs: &EnvLoader, entries: Vec<Entry>, progress: &mut dyn FnMut(ProgressEvent)) -> (Vec<(String, usize)>, Vec<String>) {
+    let mut set = Vec::new();
+    let mut unset = Vec::new();
+
+    apply_entries(envs, &entries, |key| std::env::var(key).is_ok(), |entry| {
+        if unsets(envs, entry) {
+            std::env::remove_var(entry.key.as_ref());
+            unset.push(entry.key.to_string());
+            return
+        }
+
+        match std::env::var(entry.key.as_ref()) {
+            Ok(existing) if appends(envs, entry) => {
+                std::env::set_var(entry.key.as_ref(), format!("{}{}{}", existing, envs.append_separator, entry.value))
+            },
+            _ => std::env::set_var(entry.key.as_ref(), entry.value.as_ref()),
+        }
+
+        progress(ProgressEvent::KeySet(entry.key.to_string()));
+        set.push((entry.key.to_string(), entry.file));
+    });
+
+    #[cfg(feature = "zeroize")]
+    for mut entry in entries {
+        if envs.secrets.iter().any(|secret| *secret == entry.key) {
+            zeroize::Zeroize::zeroize(entry.value.to_mut());
+        }
+    }
+
+    (set, unset)
+}
+
+
+/// The steps fn activate, fn apply_to and fn run_command run between loading the entries and applying them:
+/// fn apply_only, fn match_existing_key_case and fn check_env_size. Current has the length of the value of
+/// every variable the entries are applied over, by name.
+fn prepare_entries(envs: &EnvLoader, entries: &mut Vec<Entry>, current: &HashMap<String, usize>, report: &mut LoadReport) -> Result<(), EnvError> {
+    if let Some(only) = &envs.apply_only {
+        entries.retain(|entry| only.iter().any(|key| *key == entry.key));
+
+        let (found, missing) = only.iter().cloned().partition(|key| entries.iter().any(|entry| &entry.key == key));
+        report.requested_found = found;
+        report.requested_missing = missing;
+    }
+
+    if envs.match_key_case {
+        match_existing_case(entries, current);
+    }
+
+    if let Some(action) = envs.env_size_action {
+        let size = env_size_after(envs, entries, current);
+
+        if size > envs.env_size_limit {
+            let err = EnvError::EnvTooLarge { size, limit: envs.env_size_limit };
+
+            match action {
+                SizeLimitAction::Warn => report.warnings.push(err.to_string()),
+                SizeLimitAction::Error => return Err(err),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+/// The length of the value of every variable in the process environment, by name, see prepare_entries.
+fn env_lengths() -> HashMap<String, usize> {
+    std::env::vars_os().map(|(key, value)| (key.to_string_lossy().into_owned(), value.len())).collect()
+}
+
+
+/// Renames every key which is not in current, but differs only in case from a single variable which is, to the
+/// name of that variable, see fn match_existing_key_case.
+fn match_existing_case(entries: &mut [Entry], current: &HashMap<String, usize>) {
+    let mut names: HashMap<String, Vec<String>> = HashMap::new();
+
+    for name in current.keys() {
+        names.entry(name.to_lowercase()).or_default().push(name.clone());
+    }
+
+    for entry in entries {
+        if current.contains_key(entry.key.as_ref()) { continue };
+
+        if let Some([existing]) = names.get(&entry.key.to_lowercase()).map(Vec::as_slice) {
+            entry.key = Cow::Owned(existing.clone());
+        }
+    }
+}
+
+
+/// The size in bytes of the variables in current once the entries are applied over them, see fn check_env_size.
+fn env_size_after(envs: &EnvLoader, entries: &[Entry], current: &HashMap<String, usize>) -> usize {
+    let var_size = |key: &str, value: usize| key.len() + value + 2;
+    let mut size: usize = current.iter().map(|(key, value)| var_size(key, *value)).sum();
+
+    apply_entries(envs, entries, |key| current.contains_key(key), |entry| {
+        if unsets(envs, entry) {
+            if let Some(existing) = current.get(entry.key.as_ref()) { size -= var_size(&entry.key, *existing) };
+            return
+        }
+
+        let value = match current.get(entry.key.as_ref()) {
+            Some(existing) => {
+                size -= var_size(&entry.key, *existing);
+
+                if appends(envs, entry) { existing + envs.append_separator.len() + entry.value.len() } else { entry.value.len() }
+            },
+            None => entry.value.len(),
+        };
+
+        size += var_size(&entry.key, value);
+    });
+
+    size
+}
+
+
+/// Whether the entry removes its variable instead of setting it, see fn unset_value_token.
+fn unsets(envs: &EnvLoader, entry: &Entry) -> bool {
+    envs.unset_token.as_deref() == Some(entry.value.as_ref())
+}
+
+
+/// Whether the value of the entry is added to the one already set, see fn append_across_reloads and
+/// Assignment::Append.
+fn appends(envs: &EnvLoader, entry: &Entry) -> bool {
+    entry.assignment == Assignment::Append || envs.append_keys.iter().any(|key| *key == entry.key)
+}
+
+
+/// Sets the entries one by one in the order they are given, which is the order they first appear in the file.
+fn apply_entries<E, S>(envs: &EnvLoader, entries: &[Entry], exists: E, mut set: S)
+where
+    E: Fn(&str) -> bool,
+    S: FnMut(&Entry),
+{
+    for entry in entries {
+        let exists = exists(&entry.key);
+
+        let apply = if exists && envs.protected.iter().any(|key| *key == entry.key) {
+            false
+        } else if appends(envs, entry) {
+            true
+        } else {
+            match entry.assignment {
+                Assignment::SetIfUnset => !exists,
+                _ => !exists || envs.overwrite,
+            }
+        };
 
-        std::env::set_var(key, value)
+        if apply { set(entry) }
     }
 }
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::{EnvError, EnvLoader};
+
+
+/// Several EnvLoaders which are run one after another over a single set of variables, and applied to the
+/// environment together at the end.
+/// 
+/// Every loader starts from the variables the loaders before it produced, which begin as the current
+/// environment. Its keys are merged in the way fn activate would merge them, with its own options, so a loader
+/// without overwriting only adds keys which are not set yet. Interpolation, env references and fn require see
+/// the merged variables as well, which lets a later loader use or check a key from an earlier one, and
+/// fn apply_only, fn match_existing_key_case and fn check_env_size work over them, see fn apply_to. Nothing is
+/// changed in the environment until every loader succeeded.
+/// 
+/// The loaders are only used for their options, so fn report and fn sources of them stay empty.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// // main.rs
+/// use env_plus::{EnvLoader, Pipeline};
+/// 
+/// fn main() {
+///     Pipeline::new()
+///     .add(EnvLoader::new().change_file(String::from("./.env_plus")))
+///     .add(
+///         EnvLoader::new()
+///         .change_file(String::from("./.env_plus.local"))
+///         .overwrite_envs(true)
+///         .interpolate(true)
+///         .require(vec![String::from("SECRET")])
+///     )
+///     .run()
+///     .unwrap();
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    loaders: Vec<EnvLoader>,
+}
+
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Adds a loader to the end of the pipeline.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, loader: EnvLoader) -> Self {
+        self.loaders.push(loader);
+
+        self
+    }
+
+    /// Runs every loader and returns the variables after the last one, without changing the environment.
+    /// Variables in the environment which are not valid unicode are left out.
+    pub fn merged(&self) -> Result<HashMap<String, String>, EnvError> {
+        self.merge(unicode_vars())
+    }
+
+    /// Runs every loader, then sets the variables which were added or changed and removes the ones which were
+    /// removed, see fn unset_value_token. The merged variables are returned.
+    pub fn run(&self) -> Result<HashMap<String, String>, EnvError> {
+        let start = unicode_vars();
+        let vars = self.merge(start.clone())?;
+
+        for key in start.keys().filter(|key| !vars.contains_key(*key)) {
+            std::env::remove_var(key);
+        }
+
+        for (key, value) in &vars {
+            if start.get(key) != Some(value) {
+                std::env::set_var(key, value);
+            }
+        }
+
+        Ok(vars)
+    }
+
+    fn merge(&self, mut vars: HashMap<String, String>) -> Result<HashMap<String, String>, EnvError> {
+        for loader in &self.loaders {
+            vars = loader.clone().apply_to(vars)?;
+        }
+
+        Ok(vars)
+    }
+}
+
+
+fn unicode_vars() -> HashMap<String, String> {
+    std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
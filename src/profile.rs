@@ -0,0 +1,120 @@
+use std::sync::Mutex;
+
+use crate::{EnvError, EnvLoader};
+
+
+/// The profiles made available to fn EnvLoader::profile by fn ProfileRegistry::register.
+static PROFILES: Mutex<Vec<(String, Profile)>> = Mutex::new(Vec::new());
+
+
+/// The files and values of one environment an application can run in, like `staging` or `production`.
+/// 
+/// The files are loaded in the order they are added, like with fn add_file. The values set with fn set
+/// replace the ones from the files, and are loaded even when none of the files has their key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    files: Vec<String>,
+    overrides: Vec<(String, String)>,
+}
+
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// Adds a file to the profile. A profile without files uses the default '.env_plus' file.
+    pub fn file(mut self, path: String) -> Self {
+        self.files.push(path);
+
+        self
+    }
+
+    /// Sets a key to a value, whatever the files say. Setting the same key again replaces the earlier value.
+    pub fn set(mut self, key: String, value: String) -> Self {
+        self.overrides.retain(|(existing, _)| *existing != key);
+        self.overrides.push((key, value));
+
+        self
+    }
+}
+
+
+/// A set of named profiles, which are registered once when the application starts and picked by name with
+/// fn EnvLoader::profile.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// // main.rs
+/// use env_plus::{EnvLoader, Profile, ProfileRegistry};
+/// 
+/// fn main() {
+///     ProfileRegistry::new()
+///     .add("staging", Profile::new().file(String::from("./.env_plus")).file(String::from("./.env_plus.staging")))
+///     .add("production", Profile::new().file(String::from("./.env_plus")).set(String::from("DEBUG"), String::from("false")))
+///     .register();
+/// 
+///     let target = std::env::args().nth(1).unwrap_or(String::from("staging"));
+/// 
+///     EnvLoader::profile(&target)
+///     .unwrap()
+///     .activate()
+///     .unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileRegistry {
+    profiles: Vec<(String, Profile)>,
+}
+
+
+impl ProfileRegistry {
+    pub fn new() -> ProfileRegistry {
+        ProfileRegistry::default()
+    }
+
+    /// Adds a profile under the name, replacing an earlier one with the same name.
+    pub fn add(mut self, name: &str, profile: Profile) -> Self {
+        self.profiles.retain(|(existing, _)| existing != name);
+        self.profiles.push((name.to_string(), profile));
+
+        self
+    }
+
+    /// Returns an EnvLoader set up with the files and values of the profile, or an EnvError::UnknownProfile
+    /// if there is no profile with the name.
+    pub fn loader(&self, name: &str) -> Result<EnvLoader, EnvError> {
+        let (_, profile) = self
+            .profiles
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .ok_or_else(|| EnvError::UnknownProfile { name: name.to_string() })?;
+
+        let mut loader = EnvLoader::new();
+
+        if !profile.files.is_empty() {
+            loader.files = profile.files.clone();
+        }
+
+        loader.overrides = profile.overrides.clone();
+
+        Ok(loader)
+    }
+
+    /// Makes the profiles available to fn EnvLoader::profile, replacing the ones registered before.
+    pub fn register(self) {
+        *PROFILES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = self.profiles;
+    }
+}
+
+
+impl EnvLoader {
+    /// Returns an EnvLoader for a profile from the registered ProfileRegistry, which can be changed further
+    /// like any other. An EnvError::UnknownProfile is returned if no profile with the name was registered.
+    pub fn profile(name: &str) -> Result<EnvLoader, EnvError> {
+        let profiles = PROFILES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+
+        ProfileRegistry { profiles }.loader(name)
+    }
+}
@@ -0,0 +1,61 @@
+use figment::value::{Dict, Map, Value};
+use figment::{Error, Metadata, Profile, Provider};
+
+use crate::EnvLoader;
+
+
+/// Lets the file be used as a source in a layered figment config.
+/// 
+/// Every key is lowercased, and a `.` in a key nests the value, so `DB.PORT=5432` becomes `db.port`.
+/// Values are read the same way figment reads environment variables, which means `5432` is a number and
+/// `[1, 2]` is an array. Everything goes into the default profile.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// use env_plus::EnvLoader;
+/// use figment::Figment;
+/// 
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     secret: String,
+/// }
+/// 
+/// fn main() {
+///     let config: Config = Figment::new()
+///     .merge(EnvLoader::new())
+///     .extract()
+///     .unwrap();
+/// }
+/// ```
+impl Provider for EnvLoader {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(format!("env_plus file `{}`", self.files.join("`, `")))
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let mut dict = Dict::new();
+
+        for (key, value) in self.parse_ordered().map_err(|err| Error::from(err.to_string()))? {
+            let value: Value = value.parse().expect("infallible");
+            let nested = figment::util::nest(&key.to_ascii_lowercase(), value)
+                .into_dict()
+                .ok_or_else(|| Error::from(format!("'{}' is not a valid key", key)))?;
+
+            merge(&mut dict, nested);
+        }
+
+        Ok(Profile::Default.collect(dict))
+    }
+}
+
+
+/// Merges the nested dict into the other one, so `db.host` and `db.port` end up in the same `db` dict.
+fn merge(dict: &mut Dict, other: Dict) {
+    for (key, value) in other {
+        match (dict.get_mut(&key), value) {
+            (Some(Value::Dict(_, existing)), Value::Dict(_, nested)) => merge(existing, nested),
+            (_, value) => { dict.insert(key, value); },
+        }
+    }
+}
@@ -1,35 +1,2232 @@
-#[cfg(test)]
-pub mod tests {
-    use super::super::EnvLoader;
+use std::borrow::Cow;
+use std::collections::HashMap;
 
-    #[test]
-    fn load_default() {
-        EnvLoader::new().activate();
+use super::{Assignment, CaseFolding, CommentBlock, ConflictPolicy, Document, EnvChange, EnvError, EnvLoader, FieldKind, FieldSpec, FormatIssue, ListFormat, OverwritePolicy, Pipeline, Precedence, Profile, ProfileRegistry, ProgressEvent, Recovery, RequireSource, SizeLimitAction, StrictInterpolation, UnterminatedQuote, convert_path_list, with_prefix};
 
-        let variable = std::env::var("SUPER_SECRET").unwrap();
+#[test]
+fn load_default() {
+    EnvLoader::new().activate().unwrap();
 
-        assert_eq!(variable, String::from("I_ate_the_chocolate"))
+    let variable = std::env::var("SUPER_SECRET").unwrap();
+
+    assert_eq!(variable, String::from("I_ate_the_chocolate"))
+}
+
+#[test]
+#[should_panic]
+fn load_default_wrong() {
+    EnvLoader::new().activate().unwrap();
+
+    std::env::var("Doesn't exist").unwrap();
+}
+
+
+#[test]
+fn load_external_file() {
+    EnvLoader::new()
+    .change_comment(String::from("#"))
+    .change_delimiter(String::from("--"))
+    .change_file(String::from("./.env"))
+    .activate().unwrap();
+
+    let new_delimiter = std::env::var("NEW_DELIMITER").unwrap();
+
+    assert_eq!(new_delimiter, String::from("is_working"))
+}
+
+
+fn fixture(name: &str, contents: &str) -> String {
+    let dir = std::env::temp_dir().join("env_plus_tests");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+
+    path.to_string_lossy().into_owned()
+}
+
+
+#[test]
+fn collapse_delimiters() {
+    let file = fixture("collapse", "COLLAPSE_TWO==v\nCOLLAPSE_THREE===v\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .collapse_delimiters(true)
+    .activate().unwrap();
+
+    assert_eq!(std::env::var("COLLAPSE_TWO").unwrap(), String::from("v"));
+    assert_eq!(std::env::var("COLLAPSE_THREE").unwrap(), String::from("v"));
+}
+
+#[test]
+fn collapse_delimiters_off() {
+    let file = fixture("collapse_off", "NO_COLLAPSE_TWO==v\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .activate().unwrap();
+
+    assert_eq!(std::env::var("NO_COLLAPSE_TWO").unwrap(), String::from("=v"));
+}
+
+#[test]
+fn collapse_multi_char_delimiter() {
+    let file = fixture("collapse_multi", "COLLAPSE_MULTI_FOUR====v\nCOLLAPSE_MULTI_THREE===v\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .change_delimiter(String::from("=="))
+    .collapse_delimiters(true)
+    .activate().unwrap();
+
+    assert_eq!(std::env::var("COLLAPSE_MULTI_FOUR").unwrap(), String::from("v"));
+    assert_eq!(std::env::var("COLLAPSE_MULTI_THREE").unwrap(), String::from("=v"));
+}
+
+#[test]
+fn activate_missing_file() {
+    let result = EnvLoader::new()
+    .change_file(String::from("./does_not_exist.env"))
+    .activate();
+
+    assert!(matches!(result, Err(EnvError::Io { .. })));
+}
+
+#[test]
+fn activate_malformed_line() {
+    let file = fixture("malformed", "MALFORMED_OK=1\nMALFORMED_LINE\n");
+
+    let result = EnvLoader::new()
+    .change_file(file)
+    .activate();
+
+    assert!(matches!(result, Err(EnvError::Malformed { line: 2, .. })));
+    assert!(std::env::var("MALFORMED_OK").is_err());
+}
+
+#[test]
+#[should_panic(expected = "Failed to load ENV file './does_not_exist.env'")]
+fn activate_or_panic_message() {
+    EnvLoader::new()
+    .change_file(String::from("./does_not_exist.env"))
+    .activate_or_panic();
+}
+
+#[test]
+fn parse_flags() {
+    let file = fixture("flags", "// Enabled features\nFLAG_A\nFLAG_B // note\n\nNOT_A_FLAG=1\n");
+
+    let flags = EnvLoader::new()
+    .change_file(file)
+    .parse_flags()
+    .unwrap();
+
+    assert_eq!(flags.len(), 2);
+    assert!(flags.contains("FLAG_A"));
+    assert!(flags.contains("FLAG_B"));
+}
+
+#[test]
+fn strip_key_token() {
+    let file = fixture("key_token", "export TOKEN_EXPORTED=1\nTOKEN_PLAIN=2\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .strip_key_token(String::from("export "))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("TOKEN_EXPORTED").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("TOKEN_PLAIN").unwrap(), String::from("2"));
+}
+
+#[test]
+fn strip_key_token_after_comment() {
+    let commented = fixture("key_token_comment", "export // TOKEN_COMMENTED=1\n");
+    let inline = fixture("key_token_inline", "TOKEN_INLINE // note=1\n");
+
+    let commented = EnvLoader::new()
+    .change_file(commented)
+    .strip_key_token(String::from("export "))
+    .activate();
+
+    let inline = EnvLoader::new()
+    .change_file(inline)
+    .activate();
+
+    assert!(matches!(commented, Err(EnvError::Malformed { line: 1, .. })));
+    assert!(matches!(inline, Err(EnvError::Malformed { line: 1, .. })));
+}
+
+#[test]
+fn uppercase_keys_ascii() {
+    let file = fixture("case_ascii", "case_ascii_\u{131}=1\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .uppercase_keys(true)
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("CASE_ASCII_\u{131}").unwrap(), String::from("1"));
+}
+
+#[test]
+fn uppercase_keys_unicode() {
+    let file = fixture("case_unicode", "case_unicode_\u{131}=1\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .uppercase_keys(true)
+    .case_folding(CaseFolding::Unicode)
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("CASE_UNICODE_I").unwrap(), String::from("1"));
+}
+
+#[test]
+fn lowercase_keys_dotted_i() {
+    let file = fixture("case_lower", "CASE_LOWER_\u{130}=1\n");
+
+    EnvLoader::new()
+    .change_file(file.clone())
+    .lowercase_keys(true)
+    .activate()
+    .unwrap();
+
+    EnvLoader::new()
+    .change_file(file)
+    .lowercase_keys(true)
+    .case_folding(CaseFolding::Unicode)
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("case_lower_\u{130}").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("case_lower_i\u{307}").unwrap(), String::from("1"));
+}
+
+#[test]
+fn parse_does_not_set() {
+    let file = fixture("parse", "PARSE_ONLY=1\nPARSE_TWICE=a\nPARSE_TWICE=b\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .parse_ordered()
+    .unwrap();
+
+    assert_eq!(vars, vec![
+        (String::from("PARSE_ONLY"), String::from("1")),
+        (String::from("PARSE_TWICE"), String::from("a")),
+    ]);
+    assert!(std::env::var("PARSE_ONLY").is_err());
+}
+
+#[test]
+fn merged_with_env_precedence() {
+    let file = fixture("merged", "MERGED_BOTH=file\nMERGED_FILE=file\n");
+    std::env::set_var("MERGED_BOTH", "os");
+
+    let loader = EnvLoader::new().change_file(file);
+    let file_preferred = loader.merged_with_env(Precedence::FilePreferred).unwrap();
+    let os_preferred = loader.merged_with_env(Precedence::OsPreferred).unwrap();
+
+    assert_eq!(file_preferred["MERGED_BOTH"], "file");
+    assert_eq!(os_preferred["MERGED_BOTH"], "os");
+    assert_eq!(os_preferred["MERGED_FILE"], "file");
+    assert_eq!(std::env::var("MERGED_BOTH").unwrap(), String::from("os"));
+    assert!(std::env::var("MERGED_FILE").is_err());
+}
+
+#[test]
+fn record_separator_semicolon() {
+    let file = fixture("records_semicolon", "RECORD_SEMI_A=1;RECORD_SEMI_B=2 // note;\nRECORD_SEMI_C=3\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .record_separator(String::from(";"))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("RECORD_SEMI_A").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("RECORD_SEMI_B").unwrap(), String::from("2 "));
+    assert_eq!(std::env::var("RECORD_SEMI_C").unwrap(), String::from("3"));
+}
+
+#[test]
+fn record_separator_nul() {
+    let file = fixture("records_nul", "RECORD_NUL_A=multi\nline\0RECORD_NUL_B=2\0");
+
+    EnvLoader::new()
+    .change_file(file)
+    .record_separator(String::from("\0"))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("RECORD_NUL_A").unwrap(), String::from("multi\nline"));
+    assert_eq!(std::env::var("RECORD_NUL_B").unwrap(), String::from("2"));
+}
+
+#[test]
+fn parse_with_source() {
+    let file = fixture("with_source", "// comment\nSOURCE_A=1\r\nSOURCE_B=2 // note\n");
+
+    let parsed = EnvLoader::new()
+    .change_file(file)
+    .parse_with_source()
+    .unwrap();
+
+    assert_eq!(parsed.entries.len(), 2);
+    assert_eq!(parsed.entries[0].line, 2);
+    assert_eq!(&parsed.source[parsed.entries[0].span.clone()], "SOURCE_A=1");
+    assert_eq!(parsed.entries[1].line, 3);
+    assert_eq!(&parsed.source[parsed.entries[1].span.clone()], "SOURCE_B=2 // note");
+    assert_eq!(parsed.entries[1].value, "2 ");
+}
+
+#[test]
+fn alias_sets_both_keys() {
+    let alias = fixture("alias", "ALIAS_OLD_NAME=1\n");
+    let canonical = fixture("alias_canonical", "ALIAS_NEW_OTHER=2\n");
+
+    EnvLoader::new()
+    .change_file(alias)
+    .alias(String::from("ALIAS_OLD_NAME"), String::from("ALIAS_NEW_NAME"))
+    .activate()
+    .unwrap();
+
+    EnvLoader::new()
+    .change_file(canonical)
+    .alias(String::from("ALIAS_OLD_OTHER"), String::from("ALIAS_NEW_OTHER"))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("ALIAS_NEW_NAME").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("ALIAS_OLD_OTHER").unwrap(), String::from("2"));
+}
+
+#[test]
+fn alias_conflict() {
+    let same = fixture("alias_same", "ALIAS_SAME_OLD=1\nALIAS_SAME_NEW=1\n");
+    let conflict = fixture("alias_conflict", "ALIAS_CONFLICT_OLD=1\nALIAS_CONFLICT_NEW=2\n");
+
+    let same = EnvLoader::new()
+    .change_file(same)
+    .alias(String::from("ALIAS_SAME_OLD"), String::from("ALIAS_SAME_NEW"))
+    .parse();
+
+    let conflict = EnvLoader::new()
+    .change_file(conflict)
+    .alias(String::from("ALIAS_CONFLICT_OLD"), String::from("ALIAS_CONFLICT_NEW"))
+    .activate();
+
+    assert!(same.is_ok());
+    assert!(matches!(conflict, Err(EnvError::AliasConflict { .. })));
+    assert!(std::env::var("ALIAS_CONFLICT_OLD").is_err());
+}
+
+#[test]
+fn limit_entries() {
+    let file = fixture("limit", "// comment\nLIMIT_A=1\n\nLIMIT_B=2\nLIMIT_C=3\nnot formatted\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .limit(2)
+    .parse_ordered()
+    .unwrap();
+
+    let keys: Vec<&str> = vars.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(keys, vec!["LIMIT_A", "LIMIT_B"]);
+}
+
+#[derive(Default)]
+struct PortSink {
+    port: u16,
+    seen: Vec<String>,
+}
+
+impl super::TypedSink for PortSink {
+    fn set_typed(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.seen.push(key.to_string());
+
+        if key == "SINK_PORT" {
+            self.port = value.parse().map_err(|_| format!("'{}' is not a port", value))?;
+        }
+
+        Ok(())
     }
+}
+
+#[test]
+fn activate_into_typed() {
+    let file = fixture("typed", "SINK_NAME=app\nSINK_PORT=8080\n");
+    let mut sink = PortSink::default();
 
-    #[test]
-    #[should_panic]
-    fn load_default_wrong() {
-        EnvLoader::new().activate();
+    EnvLoader::new()
+    .change_file(file)
+    .activate_into_typed(&mut sink)
+    .unwrap();
 
-        std::env::var("Doesn't exist").unwrap();
+    assert_eq!(sink.port, 8080);
+    assert_eq!(sink.seen, vec!["SINK_NAME", "SINK_PORT"]);
+    assert!(std::env::var("SINK_PORT").is_err());
+}
+
+#[test]
+fn activate_into_typed_error() {
+    let file = fixture("typed_error", "SINK_NAME=app\n\nSINK_PORT=eighty\n");
+    let mut sink = PortSink::default();
+
+    let result = EnvLoader::new()
+    .change_file(file)
+    .activate_into_typed(&mut sink);
+
+    match result {
+        Err(EnvError::Sink { line, key, message }) => {
+            assert_eq!(line, 3);
+            assert_eq!(key, "SINK_PORT");
+            assert_eq!(message, "'eighty' is not a port");
+        },
+        _ => panic!("expected a sink error"),
     }
+}
 
+#[test]
+fn require_suggests_typo() {
+    let file = fixture("require_typo", "REQUIRE_DATABSE_URL=postgres://localhost\nREQUIRE_OTHER=1\n");
 
-    #[test]
-    fn load_external_file() {
-        EnvLoader::new()
-        .change_comment(String::from("#"))
-        .change_delimiter(String::from("--"))
-        .change_file(String::from("./.env"))
-        .activate();
+    let result = EnvLoader::new()
+    .change_file(file.clone())
+    .require(vec![String::from("REQUIRE_DATABASE_URL")])
+    .activate();
+
+    let err = result.unwrap_err();
+    assert!(matches!(&err, EnvError::MissingRequired { suggestion: Some(s), .. } if s == "REQUIRE_DATABSE_URL"));
+    assert_eq!(format!("{}", err), "Required key 'REQUIRE_DATABASE_URL' is missing, did you mean 'REQUIRE_DATABSE_URL'?");
+
+    let result = EnvLoader::new()
+    .change_file(file)
+    .require(vec![String::from("REQUIRE_DATABASE_URL")])
+    .typo_distance(0)
+    .parse();
+
+    assert!(matches!(result, Err(EnvError::MissingRequired { suggestion: None, .. })));
+}
+
+#[test]
+fn require_satisfied() {
+    let file = fixture("require_ok", "REQUIRE_FROM_FILE=1\n");
+    std::env::set_var("REQUIRE_FROM_ENV", "1");
+
+    let result = EnvLoader::new()
+    .change_file(file)
+    .require(vec![String::from("REQUIRE_FROM_FILE"), String::from("REQUIRE_FROM_ENV")])
+    .activate();
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn levenshtein_distance() {
+    assert_eq!(super::levenshtein("DATABASE_URL", "DATABSE_URL"), 1);
+    assert_eq!(super::levenshtein("kitten", "sitting"), 3);
+    assert_eq!(super::levenshtein("", "abc"), 3);
+}
+
+#[test]
+fn apply_pairs() {
+    std::env::set_var("PAIRS_KEEP", "existing");
+
+    EnvLoader::new()
+    .uppercase_keys(true)
+    .apply_pairs(vec![
+        (String::from("pairs_new"), String::from("1")),
+        (String::from("pairs_keep"), String::from("2")),
+    ])
+    .unwrap();
+
+    assert_eq!(std::env::var("PAIRS_NEW").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("PAIRS_KEEP").unwrap(), String::from("existing"));
+}
+
+#[test]
+fn apply_pairs_validates() {
+    let result = EnvLoader::new()
+    .require(vec![String::from("PAIRS_REQUIRED")])
+    .apply_pairs(vec![(String::from("PAIRS_REQUIRD"), String::from("1"))]);
+
+    assert!(matches!(result, Err(EnvError::MissingRequired { suggestion: Some(_), .. })));
+    assert!(std::env::var("PAIRS_REQUIRD").is_err());
+}
+
+#[test]
+fn apply_pairs_apply_only() {
+    EnvLoader::new()
+    .apply_only(vec![String::from("PAIRS_ONLY_KEPT")])
+    .apply_pairs(vec![
+        (String::from("PAIRS_ONLY_KEPT"), String::from("1")),
+        (String::from("PAIRS_ONLY_LEFT_OUT"), String::from("2")),
+    ])
+    .unwrap();
+
+    assert_eq!(std::env::var("PAIRS_ONLY_KEPT").unwrap(), "1");
+    assert!(std::env::var("PAIRS_ONLY_LEFT_OUT").is_err());
+}
+
+#[test]
+fn apply_in_file_order() {
+    let file = fixture("order", "ORDER_C=1\nORDER_A=2\nORDER_OLD=3\nORDER_B=4\nORDER_A=5\n");
+    let loader = EnvLoader::new()
+    .change_file(file)
+    .overwrite_envs(true)
+    .alias(String::from("ORDER_OLD"), String::from("ORDER_NEW"));
+
+    let entries = super::load_entries(&loader).unwrap();
+    let mut applied = Vec::new();
+    super::apply_entries(&loader, &entries, |_| false, |entry| applied.push(format!("{}={}", entry.key, entry.value)));
+
+    assert_eq!(applied, vec!["ORDER_C=1", "ORDER_A=5", "ORDER_OLD=3", "ORDER_NEW=3", "ORDER_B=4"]);
+}
+
+#[test]
+fn expand_paths_in_values() {
+    let file = fixture("expand_paths", concat!(
+        "EXPAND_TILDE=~/.cache/app\n",
+        "EXPAND_VAR=$EXPAND_BASE/logs\n",
+        "EXPAND_BRACES=${EXPAND_BASE}/data\n",
+        "EXPAND_MISSING=$EXPAND_NOT_SET/x\n",
+        "EXPAND_PRICE=costs $EXPAND_BASE\n",
+        "EXPAND_URL=https://example.com/$EXPAND_BASE\n",
+    ));
+    std::env::set_var("EXPAND_BASE", "/srv");
+
+    EnvLoader::new()
+    .change_file(file)
+    .change_comment(String::from("#"))
+    .expand_paths_in_values(true)
+    .activate()
+    .unwrap();
+
+    let home = std::env::var("HOME").unwrap();
+    assert_eq!(std::env::var("EXPAND_TILDE").unwrap(), format!("{}/.cache/app", home));
+    assert_eq!(std::env::var("EXPAND_VAR").unwrap(), String::from("/srv/logs"));
+    assert_eq!(std::env::var("EXPAND_BRACES").unwrap(), String::from("/srv/data"));
+    assert_eq!(std::env::var("EXPAND_MISSING").unwrap(), String::from("$EXPAND_NOT_SET/x"));
+    assert_eq!(std::env::var("EXPAND_PRICE").unwrap(), String::from("costs $EXPAND_BASE"));
+    assert_eq!(std::env::var("EXPAND_URL").unwrap(), String::from("https://example.com/$EXPAND_BASE"));
+}
+
+#[test]
+fn config_hash() {
+    let first = fixture("hash_first", "HASH_A=1\nHASH_B=2\n");
+    let reordered = fixture("hash_reordered", "// comment\nHASH_B=2\nHASH_A=1\n");
+    let changed = fixture("hash_changed", "HASH_A=1\nHASH_B=3\n");
+
+    let hash = |file: String| EnvLoader::new().change_file(file).config_hash().unwrap();
+
+    assert_eq!(hash(first.clone()), hash(reordered));
+    assert_ne!(hash(first.clone()), hash(changed));
+    assert_eq!(hash(first), 0x85df_ce22_625d_e21b);
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn mark_secret_still_sets() {
+    let file = fixture("secret", "SECRET_ZEROIZED=hunter2\nSECRET_PLAIN=1\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .mark_secret(vec![String::from("SECRET_ZEROIZED")])
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("SECRET_ZEROIZED").unwrap(), String::from("hunter2"));
+    assert_eq!(std::env::var("SECRET_PLAIN").unwrap(), String::from("1"));
+}
+
+#[test]
+fn strip_then_add_prefix() {
+    let file = fixture("prefix", "OLD_PREFIX_FOO=1\nPREFIX_BAR=2\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .add_prefix(String::from("NEW_"))
+    .strip_prefix(String::from("OLD_"))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("NEW_PREFIX_FOO").unwrap(), String::from("1"));
+    assert_eq!(std::env::var("NEW_PREFIX_BAR").unwrap(), String::from("2"));
+    assert!(std::env::var("OLD_PREFIX_FOO").is_err());
+}
+
+#[test]
+fn parse_quotes() {
+    let file = fixture("quotes", "QUOTED_DOUBLE=\"a // b\" // note\nQUOTED_SINGLE= 'c=d'\nQUOTED_NONE=e // f\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .parse_quotes(true)
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("QUOTED_DOUBLE").unwrap(), String::from("a // b"));
+    assert_eq!(std::env::var("QUOTED_SINGLE").unwrap(), String::from("c=d"));
+    assert_eq!(std::env::var("QUOTED_NONE").unwrap(), String::from("e "));
+}
+
+#[test]
+fn unterminated_quote_policies() {
+    let file = fixture("unterminated", "KEY=\"oops // note\n");
+    let parse = |policy: UnterminatedQuote| EnvLoader::new()
+        .change_file(file.clone())
+        .parse_quotes(true)
+        .unterminated_quote_policy(policy)
+        .parse();
+
+    assert!(matches!(parse(UnterminatedQuote::Error), Err(EnvError::UnterminatedQuote { line: 1, .. })));
+    assert_eq!(parse(UnterminatedQuote::Literal).unwrap()["KEY"], "\"oops ");
+    assert_eq!(parse(UnterminatedQuote::ToEndOfLine).unwrap()["KEY"], "oops // note");
+}
+
+#[cfg(feature = "figment")]
+#[test]
+fn figment_provider() {
+    #[derive(serde::Deserialize)]
+    struct Db {
+        host: String,
+        port: u16,
+    }
 
-        let new_delimiter = std::env::var("NEW_DELIMITER").unwrap();
+    #[derive(serde::Deserialize)]
+    struct Config {
+        name: String,
+        db: Db,
+    }
+
+    let file = fixture("figment", "NAME=app\nDB.HOST=localhost\nDB.PORT=5432\n");
+    let config: Config = figment::Figment::new()
+    .merge(EnvLoader::new().change_file(file))
+    .extract()
+    .unwrap();
+
+    assert_eq!(config.name, "app");
+    assert_eq!(config.db.host, "localhost");
+    assert_eq!(config.db.port, 5432);
+}
+
+#[test]
+fn multiple_files_and_sources() {
+    let base = fixture("sources_base", "SOURCES_BASE=base\nSOURCES_SHARED=base\nSOURCES_KEPT=base\n");
+    let local = fixture("sources_local", "SOURCES_SHARED=local\nSOURCES_LOCAL=local\nSOURCES_LOCAL=again\n");
+    std::env::set_var("SOURCES_KEPT", "existing");
+
+    let mut loader = EnvLoader::new()
+    .change_file(base.clone())
+    .add_file(local.clone());
+
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("SOURCES_SHARED").unwrap(), String::from("local"));
+    assert_eq!(std::env::var("SOURCES_LOCAL").unwrap(), String::from("local"));
+    assert_eq!(std::env::var("SOURCES_KEPT").unwrap(), String::from("existing"));
+
+    let sources = loader.sources();
+    assert_eq!(sources["SOURCES_BASE"], std::path::PathBuf::from(&base));
+    assert_eq!(sources["SOURCES_SHARED"], std::path::PathBuf::from(&local));
+    assert!(!sources.contains_key("SOURCES_KEPT"));
+}
+
+#[test]
+fn shell_compat_mixed() {
+    let file = fixture("shell_compat", concat!(
+        "export COMPAT_EXPORTED=1\n",
+        "COMPAT_SPACE two words\n",
+        "COMPAT_SPACE_DELIM value=x\n",
+        "COMPAT_AROUND = 3 // note\n",
+        "export\tCOMPAT_TAB=4\n",
+        "COMPAT_PLAIN=a b\n",
+        "exported_COMPAT=5\n",
+    ));
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .shell_compat(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars["COMPAT_EXPORTED"], "1");
+    assert_eq!(vars["COMPAT_SPACE"], "two words");
+    assert_eq!(vars["COMPAT_SPACE_DELIM"], "value=x");
+    assert_eq!(vars["COMPAT_AROUND"], "3 ");
+    assert_eq!(vars["COMPAT_TAB"], "4");
+    assert_eq!(vars["COMPAT_PLAIN"], "a b");
+    assert_eq!(vars["exported_COMPAT"], "5");
+}
+
+#[test]
+fn newer_file_wins() {
+    let newer = fixture("mtime_newer", "MTIME_KEY=newer\n");
+    let older = fixture("mtime_older", "MTIME_KEY=older\n");
+
+    let now = std::time::SystemTime::now();
+    let set_modified = |path: &str, time| std::fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    set_modified(&newer, now);
+    set_modified(&older, now - std::time::Duration::from_secs(60));
+
+    let by_order = EnvLoader::new()
+    .change_file(newer.clone())
+    .add_file(older.clone())
+    .parse()
+    .unwrap();
+
+    let by_mtime = EnvLoader::new()
+    .change_file(newer)
+    .add_file(older)
+    .overwrite_policy(OverwritePolicy::NewerFileWins)
+    .parse()
+    .unwrap();
+
+    assert_eq!(by_order["MTIME_KEY"], "older");
+    assert_eq!(by_mtime["MTIME_KEY"], "newer");
+}
+
+#[test]
+fn parse_documented() {
+    let file = fixture("documented", concat!(
+        "// The port to listen on.\n",
+        "//   Needs root below 1024.\n",
+        "DOC_PORT=8080\n",
+        "DOC_HOST=localhost // not a doc comment\n",
+        "// Floating comment\n",
+        "\n",
+        "DOC_NAME=app\n",
+        "// Trailing comment\n",
+    ));
+
+    let documentation = EnvLoader::new()
+    .change_file(file)
+    .parse_documented()
+    .unwrap();
+
+    let entries = &documentation.entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].doc, vec!["The port to listen on.", "Needs root below 1024."]);
+    assert!(entries[1].doc.is_empty());
+    assert_eq!(entries[2].key, "DOC_NAME");
+    assert_eq!(entries[2].line, 7);
+    assert!(entries[2].doc.is_empty());
+
+    assert_eq!(documentation.comments, vec![
+        CommentBlock { line: 5, lines: vec![String::from("Floating comment")] },
+        CommentBlock { line: 8, lines: vec![String::from("Trailing comment")] },
+    ]);
+}
+
+#[test]
+fn trim_indented_keys() {
+    let file = fixture("trim", "    TRIM_SPACES = a \n\t\tTRIM_TABS\t=\tb // note\n \t TRIM_MIXED=c\nTRIM_QUOTED = \" d \"\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file.clone())
+    .trim_whitespace(true)
+    .parse_quotes(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars["TRIM_SPACES"], "a");
+    assert_eq!(vars["TRIM_TABS"], "b");
+    assert_eq!(vars["TRIM_MIXED"], "c");
+    assert_eq!(vars["TRIM_QUOTED"], " d ");
+
+    let untrimmed = EnvLoader::new()
+    .change_file(file)
+    .parse()
+    .unwrap();
+
+    assert!(untrimmed.contains_key("    TRIM_SPACES "));
+}
+
+#[test]
+fn load_report() {
+    let file = fixture("report", "REPORT_NEW=1\nREPORT_EXISTING=2\n");
+    std::env::set_var("REPORT_EXISTING", "existing");
+
+    let mut loader = EnvLoader::new().change_file(file.clone());
+    loader.activate().unwrap();
+
+    let report = loader.report();
+    assert_eq!(report.files, vec![std::path::PathBuf::from(&file)]);
+    assert_eq!(report.parsed, 2);
+    assert_eq!(report.set, vec!["REPORT_NEW"]);
+    assert_eq!(report.skipped, vec!["REPORT_EXISTING"]);
+    assert!(report.errors.is_empty());
+
+    let mut failing = EnvLoader::new().change_file(String::from("./does_not_exist.env"));
+    assert!(failing.activate().is_err());
+    assert_eq!(failing.report().errors.len(), 1);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn report_json() {
+    let file = fixture("report_json", "REPORT_JSON_KEY=1\n");
+
+    let mut loader = EnvLoader::new().change_file(file);
+    loader.activate().unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&loader.report_json().unwrap()).unwrap();
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["success"], true);
+    assert_eq!(json["counts"]["set"], 1);
+    assert_eq!(json["set_keys"][0], "REPORT_JSON_KEY");
+}
+
+#[test]
+fn separator_lines() {
+    let file = fixture("separators", "================\nSEPARATOR_KEY=1\n  ----  // section\n");
+
+    let ignored = EnvLoader::new()
+    .change_file(file.clone())
+    .separator_chars(vec!['=', '-'])
+    .parse()
+    .unwrap();
+
+    let default = EnvLoader::new()
+    .change_file(file)
+    .parse();
+
+    assert_eq!(ignored.len(), 1);
+    assert_eq!(ignored["SEPARATOR_KEY"], "1");
+    assert!(matches!(default, Err(EnvError::Malformed { line: 1, .. })));
+}
+
+#[test]
+fn apply_only() {
+    let file = fixture("apply_only", "ONLY_WANTED=1\nONLY_IGNORED=2\n");
+
+    let mut loader = EnvLoader::new()
+    .change_file(file)
+    .apply_only(vec![String::from("ONLY_WANTED"), String::from("ONLY_ABSENT")]);
+
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("ONLY_WANTED").unwrap(), String::from("1"));
+    assert!(std::env::var("ONLY_IGNORED").is_err());
+    assert_eq!(loader.report().requested_found, vec!["ONLY_WANTED"]);
+    assert_eq!(loader.report().requested_missing, vec!["ONLY_ABSENT"]);
+}
+
+#[test]
+fn on_error_recovery() {
+    let file = fixture("on_error", "RECOVER_GOOD=1\nRECOVER_BROKEN\nRECOVER_BAD\nRECOVER_LAST=3\n");
+
+    EnvLoader::new()
+    .change_file(file.clone())
+    .on_error(|line, content, _| match line {
+        2 => Recovery::Replace(format!("{}=fixed", content)),
+        _ => Recovery::Skip,
+    })
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("RECOVER_BROKEN").unwrap(), String::from("fixed"));
+    assert!(std::env::var("RECOVER_BAD").is_err());
+    assert_eq!(std::env::var("RECOVER_LAST").unwrap(), String::from("3"));
+
+    let result = EnvLoader::new()
+    .change_file(file)
+    .on_error(|_, _, _| Recovery::Abort)
+    .parse();
+
+    assert!(matches!(result, Err(EnvError::Malformed { line: 2, .. })));
+}
+
+#[test]
+fn add_suffix() {
+    let file = fixture("add_suffix", "SUFFIX_port=8080\n");
+
+    let mut loader = EnvLoader::new()
+    .change_file(file)
+    .strip_prefix(String::from("SUFFIX_"))
+    .uppercase_keys(true)
+    .add_prefix(String::from("SUFFIXED_"))
+    .add_suffix(String::from("_INSTANCE2"));
+
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("SUFFIXED_PORT_INSTANCE2").unwrap(), String::from("8080"));
+    assert_eq!(loader.report().set, vec!["SUFFIXED_PORT_INSTANCE2"]);
+}
+
+#[test]
+fn greedy_values() {
+    let file = fixture("greedy_values", "GREEDY_BLOB=first\nsecond line\n  a = b\n\n// a comment\nthird\nGREEDY_NEXT=1\n");
+
+    let parsed = EnvLoader::new()
+    .change_file(file.clone())
+    .greedy_values(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(parsed.get("GREEDY_BLOB").unwrap(), "first\nsecond line\n  a = b\nthird");
+    assert_eq!(parsed.get("GREEDY_NEXT").unwrap(), "1");
+
+    let leading = fixture("greedy_values_leading", "not a key\nGREEDY_AFTER=1\n");
+    let result = EnvLoader::new().change_file(leading).greedy_values(true).parse();
+    assert!(matches!(result, Err(EnvError::Malformed { line: 1, .. })));
+
+    assert!(EnvLoader::new().change_file(file).parse().is_err());
+}
+
+#[test]
+fn strict_utf8() {
+    let bom = fixture("strict_utf8_bom", "\u{feff}UTF8_BOM_KEY=1\n");
+
+    let parsed = EnvLoader::new().change_file(bom.clone()).parse().unwrap();
+    assert_eq!(parsed.get("UTF8_BOM_KEY").unwrap(), "1");
+
+    let result = EnvLoader::new().change_file(bom).strict_utf8(true).parse();
+    assert!(matches!(result, Err(EnvError::Encoding { .. })));
+
+    let invalid = std::env::temp_dir().join("env_plus_tests").join("strict_utf8_invalid");
+    std::fs::write(&invalid, b"UTF8_INVALID=\xff\n").unwrap();
+    let invalid = invalid.to_str().unwrap().to_string();
+
+    let result = EnvLoader::new().change_file(invalid.clone()).parse();
+    assert!(matches!(result, Err(EnvError::Io { .. })));
 
-        assert_eq!(new_delimiter, String::from("is_working"))
+    let result = EnvLoader::new().change_file(invalid).strict_utf8(true).parse();
+    assert!(matches!(result, Err(EnvError::Encoding { .. })));
+}
+
+#[test]
+fn simple_interpolation() {
+    let file = fixture("simple_interpolation", "SIMPLE_BASE=http:/host\nSIMPLE_FULL=$SIMPLE_BASE/api/$SIMPLE_VER\nSIMPLE_VER=v1\nSIMPLE_MID=a$SIMPLE_BASE\nSIMPLE_AGAIN=$SIMPLE_FULL/$SIMPLE_VER\nSIMPLE_UNKNOWN=$NOPE/x\n");
+
+    let parsed = EnvLoader::new()
+    .change_file(file.clone())
+    .simple_interpolation(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(parsed.get("SIMPLE_FULL").unwrap(), "http:/host/api/$SIMPLE_VER");
+    assert_eq!(parsed.get("SIMPLE_MID").unwrap(), "a$SIMPLE_BASE");
+    assert_eq!(parsed.get("SIMPLE_AGAIN").unwrap(), "http:/host/api/$SIMPLE_VER/v1");
+    assert_eq!(parsed.get("SIMPLE_UNKNOWN").unwrap(), "$NOPE/x");
+
+    let parsed = EnvLoader::new().change_file(file).parse().unwrap();
+    assert_eq!(parsed.get("SIMPLE_FULL").unwrap(), "$SIMPLE_BASE/api/$SIMPLE_VER");
+}
+
+#[test]
+fn diff_files() {
+    let old = fixture("diff_files_old", "DIFF_KEEP=1\nDIFF_GONE=2\nDIFF_EDIT=3\n");
+    let new = fixture("diff_files_new", "DIFF_NEW=4\nDIFF_EDIT=5\nDIFF_KEEP=1\n");
+
+    let changes = EnvLoader::new().diff_files(&old, &new).unwrap();
+
+    assert_eq!(changes, vec![
+        EnvChange::Removed { key: String::from("DIFF_GONE"), value: String::from("2") },
+        EnvChange::Changed { key: String::from("DIFF_EDIT"), old: String::from("3"), new: String::from("5") },
+        EnvChange::Added { key: String::from("DIFF_NEW"), value: String::from("4") },
+    ]);
+}
+
+#[test]
+fn interpolate() {
+    std::env::set_var("INTERP_FROM_ENV", "env");
+
+    let file = fixture("interpolate", "INTERP_A=a\nINTERP_B=${INTERP_A}-${INTERP_FROM_ENV}-${INTERP_NONE}-${INTERP_OPEN\n");
+
+    let parsed = EnvLoader::new()
+    .change_file(file)
+    .interpolate(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(parsed.get("INTERP_B").unwrap(), "a-env--${INTERP_OPEN");
+}
+
+#[test]
+fn max_expanded_value_length() {
+    let file = fixture("expansion_bomb", "BOMB_A=0123456789\nBOMB_B=${BOMB_A}${BOMB_A}${BOMB_A}\n");
+
+    let loader = EnvLoader::new()
+    .change_file(file)
+    .interpolate(true);
+
+    assert_eq!(loader.clone().max_expanded_value_length(30).parse().unwrap()["BOMB_B"].len(), 30);
+
+    match loader.max_expanded_value_length(29).parse() {
+        Err(EnvError::ExpansionTooLong { line, key, limit }) => {
+            assert_eq!((line, key.as_str(), limit), (2, "BOMB_B", 29));
+        },
+        other => panic!("expected ExpansionTooLong, got {:?}", other),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn read_buffers_borrow() {
+    let file = fixture("read_buffers", "BUFFER_PLAIN=plain\nBUFFER_QUOTED=\"quoted\"\nBUFFER_REF=${BUFFER_PLAIN}\n");
+
+    let buffers = EnvLoader::new()
+    .change_file(file)
+    .parse_quotes(true)
+    .interpolate(true)
+    .read_buffers()
+    .unwrap();
+
+    let entries = buffers.parse().unwrap();
+
+    assert!(entries.iter().all(|(key, _)| matches!(key, Cow::Borrowed(_))));
+    assert!(matches!(entries[0].1, Cow::Borrowed("plain")));
+    assert!(matches!(entries[1].1, Cow::Borrowed("quoted")));
+    assert!(matches!(&entries[2].1, Cow::Owned(value) if value == "plain"));
+
+    let upper = EnvLoader::new()
+    .change_file(fixture("read_buffers_upper", "buffer_lower=1\n"))
+    .uppercase_keys(true)
+    .read_buffers()
+    .unwrap();
+
+    assert!(matches!(&upper.parse().unwrap()[0].0, Cow::Owned(key) if key == "BUFFER_LOWER"));
+}
+
+#[test]
+fn run_command_env() {
+    std::env::set_var("COMMAND_INHERITED", "process");
+
+    let file = fixture("run_command", "COMMAND_NEW=1\nCOMMAND_INHERITED=file\nCOMMAND_EXPLICIT=file\nCOMMAND_REMOVED=file\n");
+
+    let mut cmd = std::process::Command::new("env");
+    cmd.env("COMMAND_EXPLICIT", "explicit").env_remove("COMMAND_REMOVED");
+
+    EnvLoader::new().change_file(file.clone()).run_command(&mut cmd).unwrap();
+
+    let envs: std::collections::HashMap<_, _> = cmd.get_envs().collect();
+    assert_eq!(envs[std::ffi::OsStr::new("COMMAND_NEW")], Some(std::ffi::OsStr::new("1")));
+    assert_eq!(envs[std::ffi::OsStr::new("COMMAND_EXPLICIT")], Some(std::ffi::OsStr::new("explicit")));
+    assert_eq!(envs[std::ffi::OsStr::new("COMMAND_REMOVED")], Some(std::ffi::OsStr::new("file")));
+    assert!(!envs.contains_key(std::ffi::OsStr::new("COMMAND_INHERITED")));
+    assert!(std::env::var("COMMAND_NEW").is_err());
+
+    let mut cmd = std::process::Command::new("env");
+    EnvLoader::new().change_file(file).overwrite_envs(true).run_command(&mut cmd).unwrap();
+    assert_eq!(cmd.get_envs().find(|(key, _)| *key == "COMMAND_INHERITED").unwrap().1, Some(std::ffi::OsStr::new("file")));
+}
+
+#[test]
+fn run_command_apply_only() {
+    let file = fixture("run_command_only", "COMMAND_ONLY_KEPT=1\nCOMMAND_ONLY_LEFT_OUT=2\n");
+
+    let mut cmd = std::process::Command::new("env");
+    EnvLoader::new().change_file(file).apply_only(vec![String::from("COMMAND_ONLY_KEPT")]).run_command(&mut cmd).unwrap();
+
+    let keys: Vec<_> = cmd.get_envs().map(|(key, _)| key.to_os_string()).collect();
+    assert_eq!(keys, vec![std::ffi::OsString::from("COMMAND_ONLY_KEPT")]);
+}
+
+#[test]
+fn char_markers() {
+    let file = fixture("char_markers", "# comment\nCHAR_KEY: value # note\nCHAR_URL: http://host\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .change_comment_char('#')
+    .change_delimiter_char(':')
+    .trim_whitespace(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars.get("CHAR_KEY").unwrap(), "value");
+    assert_eq!(vars.get("CHAR_URL").unwrap(), "http://host");
+}
+
+#[test]
+fn validate_schema() {
+    let file = fixture("validate_schema", "SCHEMA_PORT=80a\nSCHEMA_LEVEL=trace\nSCHEMA_DEBUG=true\n");
+
+    let schema = vec![
+        FieldSpec { name: String::from("SCHEMA_PORT"), required: true, kind: FieldKind::Integer, ..Default::default() },
+        FieldSpec {
+            name: String::from("SCHEMA_LEVEL"),
+            allowed_values: vec![String::from("debug"), String::from("info")],
+            ..Default::default()
+        },
+        FieldSpec { name: String::from("SCHEMA_DEBUG"), kind: FieldKind::Bool, ..Default::default() },
+        FieldSpec { name: String::from("SCHEMA_MISSING"), required: true, ..Default::default() },
+        FieldSpec { name: String::from("SCHEMA_OPTIONAL"), ..Default::default() },
+    ];
+
+    let errors = EnvLoader::new().change_file(file).validate_schema(&schema).unwrap_err();
+
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(&errors[0], EnvError::InvalidValue { line: 1, key, .. } if key == "SCHEMA_PORT"));
+    assert!(matches!(&errors[1], EnvError::InvalidValue { line: 2, key, .. } if key == "SCHEMA_LEVEL"));
+    assert!(matches!(&errors[2], EnvError::MissingRequired { key, .. } if key == "SCHEMA_MISSING"));
+
+    let valid = fixture("validate_schema_valid", "SCHEMA_PORT=80\nSCHEMA_MISSING=here\n");
+    assert!(EnvLoader::new().change_file(valid).validate_schema(&schema).is_ok());
+}
+
+#[test]
+fn normalize_key_separators() {
+    let file = fixture("key_separators", "SEP.DB.HOST=dotted\nSEP-DB-PORT=5432\nSEP_DB_HOST=underscored\n");
+
+    let mut loader = EnvLoader::new()
+    .change_file(file)
+    .normalize_key_separators(vec!['.', '-']);
+
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("SEP_DB_HOST").unwrap(), String::from("dotted"));
+    assert_eq!(std::env::var("SEP_DB_PORT").unwrap(), String::from("5432"));
+    assert_eq!(loader.report().warnings, vec!["'SEP.DB.HOST' on line 1 and 'SEP_DB_HOST' on line 3 are both loaded as 'SEP_DB_HOST'."]);
+}
+
+#[test]
+fn measure_load_time() {
+    let file = fixture("measure_load_time", "TIMED_KEY=1\n");
+
+    let mut loader = EnvLoader::new().change_file(file);
+    loader.activate().unwrap();
+    assert!(loader.report().elapsed.is_none());
+
+    let mut loader = loader.measure_load_time(true);
+    loader.activate().unwrap();
+    assert!(loader.report().elapsed.is_some());
+}
+
+#[test]
+fn bracketed_lists() {
+    let file = fixture("bracketed_lists", "LIST_HOSTS=[a, b , c]\nLIST_EMPTY=[]\nLIST_PLAIN=a,b\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file.clone())
+    .parse_bracketed_lists(true)
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars.get("LIST_HOSTS").unwrap(), "a,b,c");
+    assert_eq!(vars.get("LIST_EMPTY").unwrap(), "");
+    assert_eq!(vars.get("LIST_PLAIN").unwrap(), "a,b");
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .parse_bracketed_lists(true)
+    .list_format(ListFormat { indexed: true, ..ListFormat::default() })
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars.get("LIST_HOSTS_0").unwrap(), "a");
+    assert_eq!(vars.get("LIST_HOSTS_2").unwrap(), "c");
+    assert!(!vars.contains_key("LIST_HOSTS"));
+    assert!(!vars.keys().any(|key| key.starts_with("LIST_EMPTY")));
+
+    let broken = fixture("bracketed_lists_broken", "LIST_BROKEN=[a, b\n");
+
+    let result = EnvLoader::new().change_file(broken.clone()).parse_bracketed_lists(true).parse();
+    assert!(matches!(result, Err(EnvError::Malformed { line: 1, .. })));
+
+    let vars = EnvLoader::new()
+    .change_file(broken)
+    .parse_bracketed_lists(true)
+    .list_format(ListFormat { strict: false, ..ListFormat::default() })
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars.get("LIST_BROKEN").unwrap(), "[a, b");
+}
+
+#[test]
+fn document_set() {
+    let source = "// header\r\nDOC_A=1 // keep\r\nDOC_B=\"old\"\r\nnot a line\r\nDOC_A=2";
+    let loader = EnvLoader::new().parse_quotes(true);
+
+    let mut document = Document::parse(source, &loader);
+    assert_eq!(document.get("DOC_B"), Some("old"));
+
+    document.set("DOC_A", "new");
+    document.set("DOC_B", "rotated");
+    document.set("DOC_C", "added");
+
+    assert_eq!(
+        document.to_string(),
+        "// header\r\nDOC_A=new // keep\r\nDOC_B=\"rotated\"\r\nnot a line\r\nDOC_A=new\r\nDOC_C=added\r\n",
+    );
+}
+
+#[test]
+fn update_key_in_file() {
+    let file = fixture("update_key", "# Secrets\nROTATE_TOKEN=old # rotated monthly\nROTATE_OTHER=1\n");
+    let loader = EnvLoader::new().change_comment_char('#');
+
+    loader.update_key_in_file(&file, "ROTATE_TOKEN", "new").unwrap();
+    loader.update_key_in_file(&file, "ROTATE_ADDED", "2").unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "# Secrets\nROTATE_TOKEN=new # rotated monthly\nROTATE_OTHER=1\nROTATE_ADDED=2\n",
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn require_secure_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let file = fixture("secure_permissions", "SECURE_KEY=1\n");
+
+    std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let result = EnvLoader::new().change_file(file.clone()).require_secure_permissions(true).parse();
+    assert!(matches!(result, Err(EnvError::InsecurePermissions { mode, .. }) if mode & 0o777 == 0o644));
+    assert!(EnvLoader::new().change_file(file.clone()).parse().is_ok());
+
+    std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+    assert!(EnvLoader::new().change_file(file).require_secure_permissions(true).parse().is_ok());
+}
+
+#[test]
+fn interpolate_across_files() {
+    let first = fixture("interpolate_first", "ACROSS_HOST=localhost\nACROSS_PORT=80\n");
+    let second = fixture("interpolate_second", "ACROSS_URL=${ACROSS_HOST}:${ACROSS_PORT}\nACROSS_PORT=8080\n");
+
+    let vars = EnvLoader::new()
+    .change_file(first)
+    .add_file(second)
+    .interpolate(true)
+    .strip_prefix(String::from("ACROSS_"))
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars.get("URL").unwrap(), "localhost:8080");
+}
+
+#[test]
+fn delimiter_in_value() {
+    let file = fixture("delimiter_in_value", "QUERY_URL=https:/host/?a=b&c=d // note\nQUERY_EQ=a=b=c\nQUERY_EMPTY==\n");
+
+    let vars = EnvLoader::new().change_file(file).parse().unwrap();
+
+    assert_eq!(vars.get("QUERY_URL").unwrap(), "https:/host/?a=b&c=d ");
+    assert_eq!(vars.get("QUERY_EQ").unwrap(), "a=b=c");
+    assert_eq!(vars.get("QUERY_EMPTY").unwrap(), "=");
+
+    let multi = fixture("delimiter_in_value_multi", "QUERY_MULTI::a::b\n");
+    let vars = EnvLoader::new().change_file(multi).change_delimiter(String::from("::")).parse().unwrap();
+    assert_eq!(vars.get("QUERY_MULTI").unwrap(), "a::b");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn parse_nested() {
+    let file = fixture("parse_nested", "NESTED_DB_HOST=localhost\nNESTED_DB_PORT=5432\nNESTED_NAME=app\n");
+
+    let tree = EnvLoader::new().change_file(file).parse_nested('_').unwrap();
+
+    assert_eq!(tree, serde_json::json!({
+        "nested": { "db": { "host": "localhost", "port": "5432" }, "name": "app" },
+    }));
+
+    let leaf_first = fixture("parse_nested_leaf", "CONFLICT_DB=x\nCONFLICT_DB_HOST=y\n");
+    match EnvLoader::new().change_file(leaf_first).parse_nested('_') {
+        Err(EnvError::NestingConflict { key, other }) => assert_eq!((key.as_str(), other.as_str()), ("CONFLICT_DB_HOST", "CONFLICT_DB")),
+        other => panic!("expected NestingConflict, got {:?}", other),
+    }
+
+    let branch_first = fixture("parse_nested_branch", "CONFLICT_DB_HOST=y\nCONFLICT_DB=x\n");
+    match EnvLoader::new().change_file(branch_first).parse_nested('_') {
+        Err(EnvError::NestingConflict { key, other }) => assert_eq!((key.as_str(), other.as_str()), ("CONFLICT_DB", "CONFLICT_DB_HOST")),
+        other => panic!("expected NestingConflict, got {:?}", other),
+    }
+}
+
+#[test]
+fn repeated_activate_state() {
+    let first = fixture("state_first", "STATE_FIRST=1\nSTATE_SHARED=1\n");
+    let second = fixture("state_second", "STATE_SECOND=2\nSTATE_SHARED=2\n");
+
+    let mut loader = EnvLoader::new().change_file(first.clone()).overwrite_envs(true);
+    loader.activate().unwrap();
+
+    let mut loader = loader.change_file(second.clone());
+    loader.activate().unwrap();
+
+    assert_eq!(loader.report().set, vec!["STATE_SECOND", "STATE_SHARED"]);
+    assert_eq!(loader.sources().len(), 3);
+    assert_eq!(loader.sources()["STATE_FIRST"], std::path::PathBuf::from(&first));
+    assert_eq!(loader.sources()["STATE_SHARED"], std::path::PathBuf::from(&second));
+
+    loader.reset_state();
+    assert!(loader.sources().is_empty());
+    assert!(loader.report().set.is_empty());
+
+    loader.activate().unwrap();
+    assert_eq!(loader.sources().len(), 2);
+}
+
+#[test]
+fn get_int_literals() {
+    let file = fixture("get_int", "INT_GROUPED=1_000_000\nINT_HEX=0xFF\nINT_OCT=0o755\nINT_BIN=-0b1010\nINT_BAD=1__0\nINT_EMPTY=0x\nINT_WORD=ten\n");
+
+    let mut loader = EnvLoader::new().change_file(file);
+    loader.activate().unwrap();
+
+    assert_eq!(loader.get_int("INT_GROUPED").unwrap(), 1_000_000);
+    assert_eq!(loader.get_int("INT_HEX").unwrap(), 255);
+    assert_eq!(loader.get_int("INT_OCT").unwrap(), 0o755);
+    assert_eq!(loader.get_int("INT_BIN").unwrap(), -10);
+
+    for key in &["INT_BAD", "INT_EMPTY", "INT_WORD"] {
+        assert!(matches!(loader.get_int(key), Err(EnvError::InvalidValue { .. })), "{}", key);
+    }
+
+    assert!(matches!(loader.get_int("INT_NOT_SET"), Err(EnvError::MissingRequired { .. })));
+}
+
+#[test]
+fn file_resolver() {
+    let file = fixture("file_resolver", "RESOLVED_KEY=1\n");
+    let resolved = file.clone();
+
+    let mut loader = EnvLoader::new().file_resolver(move || Ok(resolved.clone()));
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("RESOLVED_KEY").unwrap(), String::from("1"));
+    assert_eq!(loader.report().files, vec![std::path::PathBuf::from(&file)]);
+
+    let mut failing = EnvLoader::new().file_resolver(|| Ok(String::from("./resolved_missing.env")));
+    assert!(matches!(failing.activate(), Err(EnvError::Io { path, .. }) if path == "./resolved_missing.env"));
+
+    let mut erroring = EnvLoader::new().file_resolver(|| Err(EnvError::Serialize { message: String::from("no region") }));
+    assert!(erroring.activate().is_err());
+    assert_eq!(erroring.report().errors.len(), 1);
+}
+
+#[test]
+fn file_resolver_clones_and_streaming() {
+    let file = fixture("file_resolver_clones", "RESOLVED_CLONE_KEY=1\n");
+    let resolved = file.clone();
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let mut loader = EnvLoader::new().file_resolver(move || {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(resolved.clone())
+    });
+    let mut clone = loader.clone();
+
+    clone.activate().unwrap();
+    loader.activate_streaming().unwrap();
+    loader.activate_streaming().unwrap();
+
+    assert_eq!(loader.report().files, vec![std::path::PathBuf::from(&file)]);
+    assert_eq!(clone.report().files, vec![std::path::PathBuf::from(&file)]);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn skip_unchanged() {
+    let file = fixture("skip_unchanged", "CACHED_KEY=1\n");
+
+    let mut loader = EnvLoader::new().change_file(file.clone()).overwrite_envs(true).skip_unchanged(true);
+
+    loader.activate().unwrap();
+    assert!(!loader.report().cache_hit);
+
+    std::env::set_var("CACHED_KEY", "changed");
+    loader.activate().unwrap();
+    assert!(loader.report().cache_hit);
+    assert_eq!(std::env::var("CACHED_KEY").unwrap(), String::from("changed"));
+
+    loader.force_reload().unwrap();
+    assert!(!loader.report().cache_hit);
+    assert_eq!(std::env::var("CACHED_KEY").unwrap(), String::from("1"));
+
+    std::fs::write(&file, "CACHED_KEY=2\n").unwrap();
+    loader.activate().unwrap();
+    assert!(!loader.report().cache_hit);
+    assert_eq!(std::env::var("CACHED_KEY").unwrap(), String::from("2"));
+}
+
+#[test]
+fn append_across_reloads() {
+    let file = fixture("append_reloads", "APPEND_LOG=first\nAPPEND_PLAIN=1\n");
+
+    let mut loader = EnvLoader::new()
+    .change_file(file)
+    .append_across_reloads(vec![String::from("APPEND_LOG")])
+    .append_separator(String::from(";"));
+
+    loader.activate().unwrap();
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("APPEND_LOG").unwrap(), String::from("first;first"));
+    assert_eq!(std::env::var("APPEND_PLAIN").unwrap(), String::from("1"));
+    assert_eq!(loader.report().set, vec!["APPEND_LOG"]);
+}
+
+#[test]
+fn check_formatting() {
+    let file = fixture("check_formatting", "FORMAT_OK=1\nFORMAT_TRAILING=1 \n \tFORMAT_MIXED=1\n\tFORMAT_TABS=1\nFORMAT_LAST=1");
+
+    let warnings = EnvLoader::new().change_file(file.clone()).check_formatting().unwrap();
+    let issues: Vec<(usize, FormatIssue)> = warnings.into_iter().map(|warning| (warning.line, warning.issue)).collect();
+
+    assert_eq!(issues, vec![
+        (2, FormatIssue::TrailingWhitespace),
+        (3, FormatIssue::MixedIndentation),
+        (5, FormatIssue::MissingFinalNewline),
+    ]);
+
+    let clean = fixture("check_formatting_clean", "FORMAT_CLEAN=1\n");
+    assert!(EnvLoader::new().change_file(clean).check_formatting().unwrap().is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn resolve_file_references() {
+    fixture("references.json", r#"{"db": {"password": "hunter2", "port": 5432}, "hosts": ["a", "b"]}"#);
+    fixture("references.toml", "[cache]\nurl = \"redis://cache\"\n\n[[replicas]]\nhost = \"r1\"\n");
+
+    let file = fixture("references", concat!(
+        "REF_PASSWORD=@json:references.json:db.password\n",
+        "REF_PORT=@json:references.json:db.port\n",
+        "REF_HOST=@json:references.json:hosts.1\n",
+        "REF_CACHE=@toml:references.toml:cache.url\n",
+        "REF_REPLICA=@toml:references.toml:replicas.0.host\n",
+        "REF_PLAIN=@not-a-reference\n",
+    ));
+
+    let vars = EnvLoader::new().change_file(file).change_comment_char('#').resolve_file_references(true).parse().unwrap();
+
+    assert_eq!(vars["REF_PASSWORD"], "hunter2");
+    assert_eq!(vars["REF_PORT"], "5432");
+    assert_eq!(vars["REF_HOST"], "b");
+    assert_eq!(vars["REF_CACHE"], "redis://cache");
+    assert_eq!(vars["REF_REPLICA"], "r1");
+    assert_eq!(vars["REF_PLAIN"], "@not-a-reference");
+
+    let missing = fixture("references_missing", "REF_OK=1\nREF_MISSING=@json:references.json:db.user\n");
+    let result = EnvLoader::new().change_file(missing).resolve_file_references(true).parse();
+    assert!(matches!(result, Err(EnvError::Reference { line: 2, .. })));
+}
+
+#[test]
+fn with_prefix_grouping() {
+    let file = fixture("with_prefix", "GROUP_DB_HOST=localhost\nGROUP_DB_PORT=5432\nGROUP_CACHE_URL=redis\n");
+    let vars = EnvLoader::new().change_file(file).parse().unwrap();
+
+    let stripped = with_prefix(&vars, "GROUP_DB_", true);
+    assert_eq!(stripped.len(), 2);
+    assert_eq!(stripped["HOST"], "localhost");
+    assert_eq!(stripped["PORT"], "5432");
+
+    let kept = with_prefix(&vars, "GROUP_CACHE_", false);
+    assert_eq!(kept.keys().collect::<Vec<_>>(), vec!["GROUP_CACHE_URL"]);
+}
+
+#[test]
+fn env_size_limit_actions() {
+    let file = fixture("env_size_limit", "ENV_SIZE_LIMIT_VALUE=a_fairly_long_value\n");
+
+    let mut loader = EnvLoader::new().change_file(file.clone()).check_env_size(SizeLimitAction::Error).env_size_limit(16);
+    assert!(matches!(loader.activate(), Err(EnvError::EnvTooLarge { limit: 16, .. })));
+    assert!(std::env::var("ENV_SIZE_LIMIT_VALUE").is_err());
+
+    let mut loader = EnvLoader::new().change_file(file).check_env_size(SizeLimitAction::Warn).env_size_limit(16);
+    loader.activate().unwrap();
+    assert_eq!(loader.report().warnings.len(), 1);
+    assert_eq!(std::env::var("ENV_SIZE_LIMIT_VALUE").unwrap(), "a_fairly_long_value");
+}
+
+#[test]
+fn assignment_operators() {
+    std::env::set_var("OPERATOR_PRESET", "from_env");
+    std::env::set_var("OPERATOR_PATH", "/bin");
+
+    let file = fixture("assignment_operators", concat!(
+        "OPERATOR_LIST=a\n",
+        "OPERATOR_LIST+=b\n",
+        "OPERATOR_DEFAULT?=first\n",
+        "OPERATOR_DEFAULT?=second\n",
+        "OPERATOR_PRESET?=from_file\n",
+        "OPERATOR_PATH+=/usr/bin\n",
+        "OPERATOR_LEFTMOST=a?=b\n",
+    ));
+
+    EnvLoader::new()
+    .change_file(file)
+    .overwrite_envs(true)
+    .assignment_operators(vec![
+        (String::from("+"), Assignment::Set),
+        (String::from("+="), Assignment::Append),
+        (String::from("?="), Assignment::SetIfUnset),
+    ])
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("OPERATOR_LIST").unwrap(), "a,b");
+    assert_eq!(std::env::var("OPERATOR_DEFAULT").unwrap(), "first");
+    assert_eq!(std::env::var("OPERATOR_PRESET").unwrap(), "from_env");
+    assert_eq!(std::env::var("OPERATOR_PATH").unwrap(), "/bin,/usr/bin");
+    assert_eq!(std::env::var("OPERATOR_LEFTMOST").unwrap(), "a?=b");
+}
+
+#[test]
+fn activate_streaming_sets_lines() {
+    std::env::set_var("STREAMING_EXISTING", "kept");
+
+    let file = fixture("activate_streaming", "\u{feff}STREAMING_FIRST=one\r\n// a comment\nSTREAMING_EXISTING=replaced\nSTREAMING_LIST=a\n  b\n");
+
+    let mut loader = EnvLoader::new().change_file(file).greedy_values(true);
+    loader.activate_streaming().unwrap();
+
+    assert_eq!(std::env::var("STREAMING_FIRST").unwrap(), "one");
+    assert_eq!(std::env::var("STREAMING_EXISTING").unwrap(), "kept");
+    assert_eq!(std::env::var("STREAMING_LIST").unwrap(), "a\n  b");
+    assert_eq!(loader.report().set, vec!["STREAMING_FIRST", "STREAMING_LIST"]);
+    assert_eq!(loader.report().skipped, vec!["STREAMING_EXISTING"]);
+}
+
+#[test]
+fn apply_to_start_environment() {
+    let file = fixture("apply_to", "APPLY_TO_NEW=new\nAPPLY_TO_EXISTING=from_file\nAPPLY_TO_APPENDED=b\n");
+    let start = HashMap::from([
+        (String::from("APPLY_TO_EXISTING"), String::from("from_start")),
+        (String::from("APPLY_TO_APPENDED"), String::from("a")),
+    ]);
+
+    let result = EnvLoader::new()
+    .change_file(file.clone())
+    .append_across_reloads(vec![String::from("APPLY_TO_APPENDED")])
+    .apply_to(start.clone())
+    .unwrap();
+
+    assert_eq!(result["APPLY_TO_NEW"], "new");
+    assert_eq!(result["APPLY_TO_EXISTING"], "from_start");
+    assert_eq!(result["APPLY_TO_APPENDED"], "a,b");
+    assert!(std::env::var("APPLY_TO_NEW").is_err());
+
+    let result = EnvLoader::new().change_file(file).overwrite_envs(true).apply_to(start).unwrap();
+    assert_eq!(result["APPLY_TO_EXISTING"], "from_file");
+}
+
+#[test]
+fn apply_to_post_parse_steps() {
+    let file = fixture("apply_to_steps", "APPLY_STEPS_A=a\nAPPLY_STEPS_B=b\napply_steps_case=c\n");
+    let loader = || EnvLoader::new().change_file(file.clone());
+
+    let result = loader().apply_only(vec![String::from("APPLY_STEPS_A")]).apply_to(HashMap::new()).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result["APPLY_STEPS_A"], "a");
+
+    let start = HashMap::from([(String::from("APPLY_STEPS_REQUIRED"), String::from("x"))]);
+    assert!(loader().require(vec![String::from("APPLY_STEPS_REQUIRED")]).apply_to(start).is_ok());
+
+    let start = HashMap::from([(String::from("Apply_Steps_Case"), String::from("old"))]);
+    let result = loader().match_existing_key_case(true).overwrite_envs(true).apply_to(start).unwrap();
+    assert_eq!(result["Apply_Steps_Case"], "c");
+    assert!(!result.contains_key("apply_steps_case"));
+
+    let result = loader().check_env_size(SizeLimitAction::Error).env_size_limit(16).apply_to(HashMap::new());
+    assert!(matches!(result, Err(EnvError::EnvTooLarge { .. })));
+}
+
+#[test]
+fn apply_to_ignores_process_env() {
+    std::env::set_var("APPLY_ISOLATED_REAL", "real");
+
+    let file = fixture("apply_to_isolated", "APPLY_ISOLATED_INTERPOLATED=${APPLY_ISOLATED_REAL}\nAPPLY_ISOLATED_REFERENCE=&APPLY_ISOLATED_REAL\n");
+    let loader = || EnvLoader::new().change_file(file.clone()).interpolate(true).env_reference_marker(Some(String::from("&")));
+
+    let result = loader().apply_to(HashMap::new()).unwrap();
+    assert_eq!(result["APPLY_ISOLATED_INTERPOLATED"], "");
+    assert!(!result.contains_key("APPLY_ISOLATED_REFERENCE"));
+
+    let required = loader().require(vec![String::from("APPLY_ISOLATED_REAL")]).apply_to(HashMap::new());
+    assert!(matches!(required, Err(EnvError::MissingRequired { .. })));
+
+    let start = HashMap::from([(String::from("APPLY_ISOLATED_REAL"), String::from("start"))]);
+    let result = loader().apply_to(start).unwrap();
+    assert_eq!(result["APPLY_ISOLATED_INTERPOLATED"], "start");
+    assert_eq!(result["APPLY_ISOLATED_REFERENCE"], "start");
+}
+
+#[test]
+fn path_list_round_trip() {
+    let unix = "/usr/local/bin:/usr/bin";
+    let windows = convert_path_list(unix, true);
+    assert_eq!(windows, "/usr/local/bin;/usr/bin");
+    assert_eq!(convert_path_list(&windows, false), unix);
+
+    let windows = "C:\\tools;D:/bin;relative";
+    let unix = convert_path_list(windows, false);
+    assert_eq!(unix, "C:\\tools:D:/bin:relative");
+    assert_eq!(convert_path_list(&unix, true), windows);
+
+    let file = fixture("path_lists", "PATH_LIST_VALUE=/a;/b:/c\nPATH_LIST_OTHER=/a;/b\n");
+    let vars = EnvLoader::new().change_file(file).normalize_path_lists(vec![String::from("PATH_LIST_VALUE")]).parse().unwrap();
+
+    let expected = if cfg!(windows) { "/a;/b;/c" } else { "/a:/b:/c" };
+    assert_eq!(vars["PATH_LIST_VALUE"], expected);
+    assert_eq!(vars["PATH_LIST_OTHER"], "/a;/b");
+}
+
+#[test]
+fn delimiters_leftmost_wins() {
+    let file = fixture("delimiters", "DELIMS_EQUALS=a:b\nDELIMS_COLON:a=b\nDELIMS_LONGEST:=value\nDELIMS_URL=host:8080\n");
+
+    let vars = EnvLoader::new()
+    .change_file(file)
+    .delimiters(vec![String::from("="), String::from(":"), String::from(":=")])
+    .parse()
+    .unwrap();
+
+    assert_eq!(vars["DELIMS_EQUALS"], "a:b");
+    assert_eq!(vars["DELIMS_COLON"], "a=b");
+    assert_eq!(vars["DELIMS_LONGEST"], "value");
+    assert_eq!(vars["DELIMS_URL"], "host:8080");
+}
+
+#[test]
+fn to_powershell_quotes() {
+    let file = fixture("to_powershell", "POWERSHELL_PLAIN=It's $HOME\nPOWERSHELL-DASH=a\u{2019}b\n");
+    let script = EnvLoader::new().change_file(file).to_powershell().unwrap();
+
+    assert_eq!(script, "$env:POWERSHELL_PLAIN = 'It''s $HOME'\n${env:POWERSHELL-DASH} = 'a\u{2019}\u{2019}b'\n");
+}
+
+#[test]
+fn strict_interpolation_levels() {
+    let empty = fixture("strict_interpolation_empty", "STRICT_EMPTY=\nSTRICT_FROM_EMPTY=a${STRICT_EMPTY}b\n");
+    let undefined = fixture("strict_interpolation_undefined", "STRICT_FROM_UNDEFINED=a${STRICT_NEVER_SET}b\n");
+
+    let load = |file: &String, level| EnvLoader::new().change_file(file.clone()).interpolate(true).strict_interpolation(level).parse();
+
+    assert_eq!(load(&empty, StrictInterpolation::Off).unwrap()["STRICT_FROM_EMPTY"], "ab");
+    assert_eq!(load(&undefined, StrictInterpolation::Off).unwrap()["STRICT_FROM_UNDEFINED"], "ab");
+
+    assert_eq!(load(&empty, StrictInterpolation::Undefined).unwrap()["STRICT_FROM_EMPTY"], "ab");
+    assert!(matches!(
+        load(&undefined, StrictInterpolation::Undefined),
+        Err(EnvError::UnresolvedReference { line: 1, ref name, .. }) if name == "STRICT_NEVER_SET"
+    ));
+
+    assert!(matches!(
+        load(&empty, StrictInterpolation::UndefinedOrEmpty),
+        Err(EnvError::UnresolvedReference { line: 2, ref name, .. }) if name == "STRICT_EMPTY"
+    ));
+    assert!(load(&undefined, StrictInterpolation::UndefinedOrEmpty).is_err());
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn archive_entry_tar() {
+    let path = std::env::temp_dir().join("env_plus_tests").join("archive.tar");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let contents = b"ARCHIVE_TAR_VALUE=from_tar\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o600);
+
+    let mut builder = tar::Builder::new(std::fs::File::create(&path).unwrap());
+    builder.append_data(&mut header, "./config/.env_plus", &contents[..]).unwrap();
+    builder.finish().unwrap();
+
+    let path = path.to_str().unwrap().to_string();
+    let vars = EnvLoader::new().change_archive_entry(path.clone(), String::from("config/.env_plus")).parse().unwrap();
+    assert_eq!(vars["ARCHIVE_TAR_VALUE"], "from_tar");
+
+    let missing = EnvLoader::new().change_archive_entry(path, String::from("other")).parse();
+    assert!(matches!(missing, Err(EnvError::MissingArchiveEntry { .. })));
+
+    let no_archive = EnvLoader::new().change_archive_entry(String::from("./no_such_archive.tar"), String::from("other")).parse();
+    assert!(matches!(no_archive, Err(EnvError::Io { .. })));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn archive_entry_zip() {
+    let path = std::env::temp_dir().join("env_plus_tests").join("archive.zip");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    writer.start_file("config/.env_plus", zip::write::SimpleFileOptions::default()).unwrap();
+    std::io::Write::write_all(&mut writer, b"ARCHIVE_ZIP_VALUE=from_zip\n").unwrap();
+    writer.finish().unwrap();
+
+    let path = path.to_str().unwrap().to_string();
+    let vars = EnvLoader::new().change_archive_entry(path.clone(), String::from("config/.env_plus")).parse().unwrap();
+    assert_eq!(vars["ARCHIVE_ZIP_VALUE"], "from_zip");
+
+    let missing = EnvLoader::new().change_archive_entry(path, String::from("other")).parse();
+    assert!(matches!(missing, Err(EnvError::MissingArchiveEntry { .. })));
+}
+
+#[test]
+fn profiles_by_name() {
+    let base = fixture("profile_base", "PROFILE_NAME=base\nPROFILE_DEBUG=true\n");
+    let staging = fixture("profile_staging", "PROFILE_NAME=staging\n");
+
+    let registry = ProfileRegistry::new()
+    .add("staging", Profile::new().file(base.clone()).file(staging))
+    .add("production", Profile::new().file(base).set(String::from("PROFILE_DEBUG"), String::from("false")).set(String::from("PROFILE_REGION"), String::from("eu")));
+
+    let vars = registry.loader("staging").unwrap().parse().unwrap();
+    assert_eq!(vars["PROFILE_NAME"], "staging");
+    assert_eq!(vars["PROFILE_DEBUG"], "true");
+
+    registry.register();
+
+    let mut loader = EnvLoader::profile("production").unwrap();
+    loader.activate().unwrap();
+    assert_eq!(std::env::var("PROFILE_NAME").unwrap(), "base");
+    assert_eq!(std::env::var("PROFILE_DEBUG").unwrap(), "false");
+    assert_eq!(std::env::var("PROFILE_REGION").unwrap(), "eu");
+    assert!(!loader.sources().contains_key("PROFILE_REGION"));
+
+    assert!(matches!(EnvLoader::profile("missing"), Err(EnvError::UnknownProfile { .. })));
+}
+
+#[test]
+fn env_reference_marker_chain() {
+    std::env::set_var("ENV_REFERENCE_OUTSIDE", "from_env");
+
+    let file = fixture("env_reference_marker", concat!(
+        "ENV_REFERENCE_HOME=/srv/app\n",
+        "ENV_REFERENCE_LOG=&ENV_REFERENCE_HOME\n",
+        "ENV_REFERENCE_CHAIN=&ENV_REFERENCE_LOG\n",
+        "ENV_REFERENCE_FROM_ENV=&ENV_REFERENCE_OUTSIDE\n",
+        "ENV_REFERENCE_MISSING=&ENV_REFERENCE_NEVER_SET\n",
+        "ENV_REFERENCE_LITERAL=a&b\n",
+    ));
+
+    let loader = EnvLoader::new().change_file(file).env_reference_marker(Some(String::from("&")));
+    let vars = loader.clone().parse().unwrap();
+
+    assert_eq!(vars["ENV_REFERENCE_LOG"], "/srv/app");
+    assert_eq!(vars["ENV_REFERENCE_CHAIN"], "/srv/app");
+    assert_eq!(vars["ENV_REFERENCE_FROM_ENV"], "from_env");
+    assert_eq!(vars["ENV_REFERENCE_LITERAL"], "a&b");
+    assert!(!vars.contains_key("ENV_REFERENCE_MISSING"));
+
+    let required = loader.require(vec![String::from("ENV_REFERENCE_MISSING")]).parse();
+    assert!(matches!(required, Err(EnvError::MissingRequired { .. })));
+}
+
+#[test]
+fn redundant_keys_match_environment() {
+    std::env::set_var("REDUNDANT_SAME", "value");
+    std::env::set_var("REDUNDANT_DIFFERENT", "old");
+
+    let file = fixture("redundant_keys", "REDUNDANT_SAME=value\nREDUNDANT_DIFFERENT=new\nREDUNDANT_NEW=value\n");
+    let mut loader = EnvLoader::new().change_file(file).overwrite_envs(true);
+    loader.activate().unwrap();
+
+    assert_eq!(loader.redundant_keys(), ["REDUNDANT_SAME"]);
+    assert_eq!(std::env::var("REDUNDANT_DIFFERENT").unwrap(), "new");
+}
+
+#[test]
+fn cr_only_line_endings() {
+    let vars = EnvLoader::new().change_file(String::from("./.env_cr")).treat_cr_as_newline(true).parse().unwrap();
+
+    assert_eq!(vars.len(), 3);
+    assert_eq!(vars["CR_ONLY_FIRST"], "one");
+    assert_eq!(vars["CR_ONLY_SECOND"], "two");
+    assert_eq!(vars["CR_ONLY_THIRD"], "three");
+
+    let mixed = fixture("cr_mixed_endings", "CR_MIXED_FIRST=one\r\nCR_MIXED_SECOND=two\rCR_MIXED_THIRD=three\n");
+    let vars = EnvLoader::new().change_file(mixed).treat_cr_as_newline(true).parse_with_source().unwrap();
+    assert_eq!(vars.entries.iter().map(|entry| entry.line).collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn dump_redacted_sorted() {
+    let file = fixture("dump_redacted", "DUMP_TOKEN=abc\nDUMP_PORT=8080\nDUMP_HOST=localhost\n");
+    let dump = EnvLoader::new().change_file(file).dump_redacted(&[String::from("DUMP_TOKEN")]).unwrap();
+
+    assert_eq!(dump, "DUMP_HOST=localhost\nDUMP_PORT=8080\nDUMP_TOKEN=***\n");
+    assert!(std::env::var("DUMP_HOST").is_err());
+}
+
+#[test]
+fn strip_invisible_chars_in_values() {
+    let file = fixture("strip_invisible", "INVISIBLE_TOKEN=ab\u{200b}c\u{feff}d\u{2060}\n");
+
+    let vars = EnvLoader::new().change_file(file.clone()).parse().unwrap();
+    assert_eq!(vars["INVISIBLE_TOKEN"], "ab\u{200b}c\u{feff}d\u{2060}");
+
+    let vars = EnvLoader::new().change_file(file).strip_invisible_chars(true).parse().unwrap();
+    assert_eq!(vars["INVISIBLE_TOKEN"], "abcd");
+}
+
+#[test]
+fn protect_existing_keys() {
+    std::env::set_var("PROTECTED_PORT", "3000");
+    std::env::set_var("PROTECTED_HOST", "old_host");
+    std::env::set_var("PROTECTED_APPENDED", "a");
+
+    let file = fixture("protect_existing", "PROTECTED_PORT=8080\nPROTECTED_HOST=new_host\nPROTECTED_APPENDED=b\nPROTECTED_UNSET=loaded\n");
+
+    EnvLoader::new()
+    .change_file(file)
+    .overwrite_envs(true)
+    .append_across_reloads(vec![String::from("PROTECTED_APPENDED")])
+    .protect_existing(vec![String::from("PROTECTED_PORT"), String::from("PROTECTED_APPENDED"), String::from("PROTECTED_UNSET")])
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("PROTECTED_PORT").unwrap(), "3000");
+    assert_eq!(std::env::var("PROTECTED_APPENDED").unwrap(), "a");
+    assert_eq!(std::env::var("PROTECTED_HOST").unwrap(), "new_host");
+    assert_eq!(std::env::var("PROTECTED_UNSET").unwrap(), "loaded");
+}
+
+#[test]
+fn activate_with_progress_events() {
+    let first = fixture("progress_first", "PROGRESS_A=1\nPROGRESS_B=2\n");
+    let second = fixture("progress_second", "PROGRESS_C=3\n");
+
+    let mut events = Vec::new();
+    EnvLoader::new()
+    .change_file(first.clone())
+    .add_file(second.clone())
+    .activate_with_progress(|event| {
+        // Each key is reported as soon as it is set, before the next one.
+        if event == ProgressEvent::KeySet(String::from("PROGRESS_A")) {
+            assert!(std::env::var("PROGRESS_A").is_ok() && std::env::var("PROGRESS_B").is_err());
+        }
+
+        events.push(event)
+    })
+    .unwrap();
+
+    assert_eq!(events, vec![
+        ProgressEvent::FileStarted(first.clone().into()),
+        ProgressEvent::FileFinished(first.into(), 2),
+        ProgressEvent::FileStarted(second.clone().into()),
+        ProgressEvent::FileFinished(second.into(), 1),
+        ProgressEvent::KeySet(String::from("PROGRESS_A")),
+        ProgressEvent::KeySet(String::from("PROGRESS_B")),
+        ProgressEvent::KeySet(String::from("PROGRESS_C")),
+    ]);
+}
+
+#[test]
+fn max_key_length_limit() {
+    let file = fixture("max_key_length", "MAX_KEY_OK=1\nMAX_KEY_THIS_ONE_IS_FAR_TOO_LONG=2\n");
+
+    let result = EnvLoader::new().change_file(file.clone()).max_key_length(16).parse();
+    assert!(matches!(result, Err(EnvError::KeyTooLong { line: 2, length: 32, limit: 16 })));
+
+    assert!(EnvLoader::new().change_file(file).max_key_length(32).parse().is_ok());
+}
+
+#[test]
+fn line_continuation_joins() {
+    let file = fixture("line_continuation", "CONTINUED_BLOB=SGVsbG8s\\\nIHdvcmxk\\\nIQ==\nCONTINUED_AFTER=next\n");
+
+    let entries = EnvLoader::new().change_file(file.clone()).line_continuation(true).continuation_join(String::new()).parse_with_source().unwrap().entries;
+    assert_eq!(entries[0].value, "SGVsbG8sIHdvcmxkIQ==");
+    assert_eq!((entries[1].key.as_str(), entries[1].value.as_str(), entries[1].line), ("CONTINUED_AFTER", "next", 4));
+
+    let vars = EnvLoader::new().change_file(file).line_continuation(true).parse().unwrap();
+    assert_eq!(vars["CONTINUED_BLOB"], "SGVsbG8s\nIHdvcmxk\nIQ==");
+}
+
+#[test]
+fn line_continuation_ignores_comments() {
+    let file = fixture("line_continuation_comments", "// windows dir C:\\\nCONTINUED_C=value\nCONTINUED_A=1 // note \\\nCONTINUED_B=2\n");
+
+    let vars = EnvLoader::new().change_file(file).line_continuation(true).parse().unwrap();
+
+    assert_eq!(vars["CONTINUED_C"], "value");
+    assert_eq!(vars["CONTINUED_A"], "1 ");
+    assert_eq!(vars["CONTINUED_B"], "2");
+}
+
+#[test]
+fn check_interpolation_reports_all() {
+    std::env::set_var("CHECK_INTERP_FROM_ENV", "set");
+
+    let file = fixture("check_interpolation", concat!(
+        "CHECK_INTERP_HOST=localhost\n",
+        "CHECK_INTERP_URL=${CHECK_INTERP_HOST}:${CHECK_INTERP_PORT}\n",
+        "CHECK_INTERP_ENV=${CHECK_INTERP_FROM_ENV}\n",
+        "CHECK_INTERP_LATER=${CHECK_INTERP_DEFINED_AFTER}\n",
+        "CHECK_INTERP_DEFINED_AFTER=1\n",
+    ));
+
+    let errors = EnvLoader::new().change_file(file.clone()).check_interpolation().unwrap_err();
+    let found: Vec<(usize, String)> = errors
+        .into_iter()
+        .map(|err| match err {
+            EnvError::UnresolvedReference { line, name, .. } => (line, name),
+            other => panic!("unexpected error {}", other),
+        })
+        .collect();
+
+    assert_eq!(found, vec![(2, String::from("CHECK_INTERP_PORT")), (4, String::from("CHECK_INTERP_DEFINED_AFTER"))]);
+    assert!(std::env::var("CHECK_INTERP_HOST").is_err());
+}
+
+#[test]
+fn shebang_and_first_line() {
+    let script = fixture("shebang", "#!/bin/sh\nSHEBANG_VALUE=1\n");
+    let entries = EnvLoader::new().change_file(script.clone()).parse_with_source().unwrap().entries;
+    assert_eq!((entries[0].key.as_str(), entries[0].line), ("SHEBANG_VALUE", 2));
+
+    let header = fixture("skip_first_line", "generated header\nFIRST_LINE_VALUE=1\n");
+    assert!(EnvLoader::new().change_file(header.clone()).parse().is_err());
+
+    let vars = EnvLoader::new().change_file(header.clone()).skip_first_line(true).parse().unwrap();
+    assert_eq!(vars["FIRST_LINE_VALUE"], "1");
+
+    let documented = EnvLoader::new().change_file(script).parse_documented().unwrap();
+    assert_eq!((documented.entries[0].key.as_str(), documented.entries[0].line), ("SHEBANG_VALUE", 2));
+
+    let documented = EnvLoader::new().change_file(header).skip_first_line(true).parse_documented().unwrap();
+    assert_eq!(documented.entries[0].key, "FIRST_LINE_VALUE");
+}
+
+#[test]
+fn document_comment_sidecar() {
+    let source = "// The server\nHOST=localhost // where it runs\n\nPORT=\"80 // not a comment\"   // the port\n";
+    let (text, sidecar) = Document::parse(source, &EnvLoader::new().parse_quotes(true)).split_comments();
+
+    assert_eq!(text, "HOST=localhost\n\nPORT=\"80 // not a comment\"\n");
+    assert_eq!(sidecar, "1\t// The server\n2\t// where it runs\n4\t// the port\n");
+
+    let path = fixture("document_sidecar", source);
+    Document::parse(source, &EnvLoader::new().parse_quotes(true)).write_without_comments(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    assert_eq!(std::fs::read_to_string(format!("{}.comments", path)).unwrap(), sidecar);
+}
+
+#[test]
+fn match_existing_key_case_path() {
+    std::env::set_var("MATCH_CASE_PATH", "/usr/bin");
+
+    let file = fixture("match_existing_key_case", "match_case_path=/opt/bin\nmatch_case_new=1\n");
+    let mut loader = EnvLoader::new().change_file(file).overwrite_envs(true).match_existing_key_case(true);
+    loader.activate().unwrap();
+
+    assert_eq!(std::env::var("MATCH_CASE_PATH").unwrap(), "/opt/bin");
+    assert_eq!(std::env::var("match_case_new").unwrap(), "1");
+    assert_eq!(loader.report().set, vec!["MATCH_CASE_PATH", "match_case_new"]);
+
+    if !cfg!(windows) {
+        assert!(std::env::var("match_case_path").is_err());
+    }
+}
+
+#[cfg(feature = "include_dir")]
+#[test]
+fn embedded_dir_in_path_order() {
+    static EMBEDDED: include_dir::Dir<'static> = include_dir::include_dir!("$CARGO_MANIFEST_DIR/embedded");
+
+    let mut loader = EnvLoader::new().load_embedded_dir(&EMBEDDED);
+    assert_eq!(loader.parse_ordered().unwrap(), vec![
+        (String::from("EMBEDDED_NAME"), String::from("local")),
+        (String::from("EMBEDDED_BASE_ONLY"), String::from("1")),
+    ]);
+
+    loader.activate().unwrap();
+    assert_eq!(loader.sources()["EMBEDDED_NAME"], std::path::PathBuf::from("local/override.env"));
+}
+
+#[test]
+fn strip_trailing_separator_after_comment() {
+    let file = fixture("trailing_separator", "TRAILING_SEP_A=value ; // note\nTRAILING_SEP_B=a;b;;\nTRAILING_SEP_C=\"quoted;\"\n");
+    let vars = EnvLoader::new().change_file(file).parse_quotes(true).strip_trailing_separator(Some(';')).parse().unwrap();
+
+    assert_eq!(vars["TRAILING_SEP_A"], "value");
+    assert_eq!(vars["TRAILING_SEP_B"], "a;b;");
+    assert_eq!(vars["TRAILING_SEP_C"], "quoted;");
+}
+
+#[test]
+fn require_status_sources() {
+    std::env::set_var("REQUIRE_STATUS_ENV", "1");
+    std::env::set_var("REQUIRE_STATUS_BOTH", "1");
+
+    let file = fixture("require_status", "REQUIRE_STATUS_FILE=1\nREQUIRE_STATUS_BOTH=2\n");
+    let status = EnvLoader::new()
+    .change_file(file)
+    .require(vec![
+        String::from("REQUIRE_STATUS_FILE"),
+        String::from("REQUIRE_STATUS_ENV"),
+        String::from("REQUIRE_STATUS_BOTH"),
+        String::from("REQUIRE_STATUS_MISSING"),
+    ])
+    .require_status()
+    .unwrap();
+
+    assert_eq!(status, vec![
+        (String::from("REQUIRE_STATUS_FILE"), RequireSource::File),
+        (String::from("REQUIRE_STATUS_ENV"), RequireSource::Environment),
+        (String::from("REQUIRE_STATUS_BOTH"), RequireSource::File),
+        (String::from("REQUIRE_STATUS_MISSING"), RequireSource::Missing),
+    ]);
+}
+
+#[test]
+fn digit_grouping_comma() {
+    let file = fixture("digit_grouping_comma", "GROUPED_COMMA=1,000,000\nGROUPED_COMMA_NEG=-12,345\nGROUPED_COMMA_BAD=1,00\n");
+
+    let mut loader = EnvLoader::new().change_file(file).digit_grouping(Some(','));
+    loader.activate().unwrap();
+
+    assert_eq!(loader.get_int("GROUPED_COMMA").unwrap(), 1_000_000);
+    assert_eq!(loader.get_int("GROUPED_COMMA_NEG").unwrap(), -12_345);
+    assert!(matches!(loader.get_int("GROUPED_COMMA_BAD"), Err(EnvError::InvalidValue { .. })));
+}
+
+#[test]
+fn digit_grouping_dot() {
+    let file = fixture("digit_grouping_dot", "GROUPED_DOT=1.000.000\nGROUPED_DOT_PLAIN=42\nGROUPED_DOT_DECIMAL=1.5\n");
+
+    let mut loader = EnvLoader::new().change_file(file.clone()).digit_grouping(Some('.'));
+    loader.activate().unwrap();
+
+    assert_eq!(loader.get_int("GROUPED_DOT").unwrap(), 1_000_000);
+    assert_eq!(loader.get_int("GROUPED_DOT_PLAIN").unwrap(), 42);
+    assert!(matches!(loader.get_int("GROUPED_DOT_DECIMAL"), Err(EnvError::InvalidValue { .. })));
+
+    let ungrouped = EnvLoader::new().change_file(file);
+    assert!(matches!(ungrouped.get_int("GROUPED_DOT"), Err(EnvError::InvalidValue { .. })));
+}
+
+#[test]
+fn unset_value_token() {
+    let base = fixture("unset_token_base", "UNSET_TOKEN_LAYERED=base\nUNSET_TOKEN_KEPT=base\n");
+    let layer = fixture("unset_token_layer", "UNSET_TOKEN_LAYERED=NONE\nUNSET_TOKEN_KEPT=NONE\n");
+
+    EnvLoader::new().change_file(base).activate().unwrap();
+
+    EnvLoader::new()
+    .change_file(layer.clone())
+    .unset_value_token(String::from("NONE"))
+    .activate()
+    .unwrap();
+
+    assert_eq!(std::env::var("UNSET_TOKEN_LAYERED").unwrap(), "base");
+
+    let mut loader = EnvLoader::new()
+    .change_file(layer)
+    .unset_value_token(String::from("NONE"))
+    .protect_existing(vec![String::from("UNSET_TOKEN_KEPT")])
+    .overwrite_envs(true);
+
+    loader.activate().unwrap();
+
+    assert!(std::env::var("UNSET_TOKEN_LAYERED").is_err());
+    assert_eq!(std::env::var("UNSET_TOKEN_KEPT").unwrap(), "base");
+
+    assert_eq!(loader.report().unset, vec!["UNSET_TOKEN_LAYERED"]);
+    assert_eq!(loader.report().skipped, vec!["UNSET_TOKEN_KEPT"]);
+    assert!(!loader.sources().contains_key("UNSET_TOKEN_LAYERED"));
+}
+
+#[test]
+fn parse_with_source_raw_lines() {
+    let file = fixture("raw_lines", "RAW_PLAIN=a // note\nRAW_JOINED=one \\\n  two\nRAW_PLAIN=b\n");
+
+    let loader = || EnvLoader::new().change_file(file.clone()).line_continuation(true);
+
+    let parsed = loader().parse_with_source().unwrap();
+    let raw = parsed.raw_lines();
+
+    assert_eq!(raw.len(), 2);
+    assert_eq!(raw["RAW_JOINED"], "RAW_JOINED=one \\\n  two");
+    assert_eq!(raw["RAW_PLAIN"], "RAW_PLAIN=a // note");
+    assert_eq!(loader().parse().unwrap()["RAW_PLAIN"], "a ");
+
+    let parsed = loader().overwrite_envs(true).parse_with_source().unwrap();
+    assert_eq!(parsed.raw_lines()["RAW_PLAIN"], "RAW_PLAIN=b");
+    assert_eq!(loader().overwrite_envs(true).parse().unwrap()["RAW_PLAIN"], "b");
+}
+
+#[test]
+fn conflict_policy() {
+    let base = fixture("conflict_base", "CONFLICT_KEY=base\nCONFLICT_SAME=1\n");
+    let local = fixture("conflict_local", "CONFLICT_KEY=local\nCONFLICT_SAME=1\n");
+    let loader = || EnvLoader::new().change_file(base.clone()).add_file(local.clone());
+
+    let mut warned = loader().conflict_policy(ConflictPolicy::Warn);
+    warned.activate().unwrap();
+
+    assert_eq!(std::env::var("CONFLICT_KEY").unwrap(), "local");
+    assert_eq!(warned.report().warnings.len(), 1);
+    assert!(warned.report().warnings[0].contains(&base) && warned.report().warnings[0].contains(&local));
+
+    match loader().conflict_policy(ConflictPolicy::Error).parse() {
+        Err(EnvError::Conflict { key, first_value, second_value, .. }) => {
+            assert_eq!((key.as_str(), first_value.as_str(), second_value.as_str()), ("CONFLICT_KEY", "base", "local"));
+        },
+        other => panic!("{:?}", other),
+    }
+
+    let mut ignored = loader();
+    ignored.activate().unwrap();
+    assert!(ignored.report().warnings.is_empty());
+}
+
+#[test]
+fn get_list_items() {
+    let file = fixture("get_list", "LIST_HOSTS=a, b ,,c,\nLIST_SPACES=x  y z\nLIST_EMPTY=\n");
+
+    let mut loader = EnvLoader::new().change_file(file);
+    loader.activate().unwrap();
+
+    assert_eq!(loader.get_list("LIST_HOSTS", ",").unwrap(), vec!["a", "b", "c"]);
+    assert_eq!(loader.get_list("LIST_SPACES", " ").unwrap(), vec!["x", "y", "z"]);
+    assert!(loader.get_list("LIST_EMPTY", ",").unwrap().is_empty());
+    assert!(matches!(loader.get_list("LIST_NOT_SET", ","), Err(EnvError::MissingRequired { .. })));
+
+    let keeping = EnvLoader::new().keep_empty_list_items(true);
+    assert_eq!(keeping.get_list("LIST_HOSTS", ",").unwrap(), vec!["a", "b", "", "c", ""]);
+    assert!(keeping.get_list("LIST_EMPTY", ",").unwrap().is_empty());
+}
+
+#[test]
+fn pipeline_merges_loaders() {
+    std::env::set_var("PIPELINE_REMOVED", "old");
+
+    let base = fixture("pipeline_base", "PIPELINE_HOST=localhost\nPIPELINE_PORT=80\n");
+    let local = fixture("pipeline_local", "PIPELINE_PORT=8080\nPIPELINE_URL=${PIPELINE_HOST}:${PIPELINE_PORT}\nPIPELINE_REMOVED=NONE\n");
+
+    let vars = Pipeline::new()
+    .add(EnvLoader::new().change_file(base))
+    .add(
+        EnvLoader::new()
+        .change_file(local)
+        .overwrite_envs(true)
+        .interpolate(true)
+        .unset_value_token(String::from("NONE"))
+        .require(vec![String::from("PIPELINE_HOST")])
+    )
+    .run()
+    .unwrap();
+
+    assert_eq!(vars["PIPELINE_URL"], "localhost:8080");
+    assert_eq!(std::env::var("PIPELINE_URL").unwrap(), "localhost:8080");
+    assert_eq!(std::env::var("PIPELINE_PORT").unwrap(), "8080");
+    assert!(std::env::var("PIPELINE_REMOVED").is_err());
+}
+
+#[test]
+fn pipeline_apply_only() {
+    let base = fixture("pipeline_only_base", "PIPELINE_ONLY_BASE=1\n");
+    let local = fixture("pipeline_only_local", "PIPELINE_ONLY_KEPT=2\nPIPELINE_ONLY_LEFT_OUT=3\n");
+
+    let vars = Pipeline::new()
+    .add(EnvLoader::new().change_file(base))
+    .add(EnvLoader::new().change_file(local).apply_only(vec![String::from("PIPELINE_ONLY_KEPT")]))
+    .merged()
+    .unwrap();
+
+    assert_eq!(vars["PIPELINE_ONLY_BASE"], "1");
+    assert_eq!(vars["PIPELINE_ONLY_KEPT"], "2");
+    assert!(!vars.contains_key("PIPELINE_ONLY_LEFT_OUT"));
+}
+
+#[test]
+fn env_reference_marker_streaming() {
+    let file = fixture("env_reference_streaming", "STREAM_REFERENCE_HOME=/srv/app\nSTREAM_REFERENCE_LOG=&STREAM_REFERENCE_HOME\nSTREAM_REFERENCE_MISSING=&STREAM_REFERENCE_NEVER_SET\n");
+
+    let mut loader = EnvLoader::new().change_file(file).env_reference_marker(Some(String::from("&")));
+    loader.activate_streaming().unwrap();
+
+    assert_eq!(std::env::var("STREAM_REFERENCE_LOG").unwrap(), "/srv/app");
+    assert!(std::env::var("STREAM_REFERENCE_MISSING").is_err());
+}
+
+#[test]
+fn match_existing_key_case_streaming() {
+    std::env::set_var("Stream_Case_Key", "old");
+
+    let file = fixture("match_case_streaming", "STREAM_CASE_KEY=new\n");
+
+    let mut loader = EnvLoader::new().change_file(file).match_existing_key_case(true).overwrite_envs(true);
+    loader.activate_streaming().unwrap();
+
+    assert_eq!(std::env::var("Stream_Case_Key").unwrap(), "new");
+    assert_eq!(loader.report().set, vec!["Stream_Case_Key"]);
+}
+
+#[test]
+fn parse_with_source_without_files() {
+    let mut loader = EnvLoader::new();
+    loader.files.clear();
+
+    assert!(matches!(loader.parse_with_source(), Err(EnvError::NoFiles)));
+}
+
+#[test]
+fn treat_cr_as_newline_streaming() {
+    let file = fixture("cr_streaming", "STREAM_CR_A=1\rSTREAM_CR_B=2\r\nSTREAM_CR_C=3\n");
+
+    let mut loader = EnvLoader::new().change_file(file).treat_cr_as_newline(true);
+    loader.activate_streaming().unwrap();
+
+    assert_eq!(std::env::var("STREAM_CR_A").unwrap(), "1");
+    assert_eq!(std::env::var("STREAM_CR_B").unwrap(), "2");
+    assert_eq!(std::env::var("STREAM_CR_C").unwrap(), "3");
+    assert_eq!(loader.report().parsed, 3);
+}
+#[cfg(feature = "zeroize")]
+#[test]
+fn conflict_redacts_secrets() {
+    let base = fixture("conflict_secret_base", "CONFLICT_SECRET=hunter2\n");
+    let local = fixture("conflict_secret_local", "CONFLICT_SECRET=hunter3\n");
+
+    let mut loader = EnvLoader::new()
+    .change_file(base)
+    .add_file(local)
+    .conflict_policy(ConflictPolicy::Warn)
+    .mark_secret(vec![String::from("CONFLICT_SECRET")]);
+    loader.activate().unwrap();
+
+    assert_eq!(loader.report().warnings.len(), 1);
+    assert!(!loader.report().warnings[0].contains("hunter"));
+}
+#[cfg(unix)]
+#[test]
+fn update_key_in_file_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let file = fixture("update_key_mode", "ROTATE_MODE=old\n");
+    std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    EnvLoader::new().update_key_in_file(&file, "ROTATE_MODE", "new").unwrap();
+
+    assert_eq!(std::fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o640);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "ROTATE_MODE=new\n");
+}
+#[test]
+fn measure_load_time_streaming() {
+    let file = fixture("measure_streaming", "MEASURE_STREAMING=1\n");
+
+    let mut loader = EnvLoader::new().change_file(file.clone()).measure_load_time(true);
+    loader.activate_streaming().unwrap();
+    assert!(loader.report().elapsed.is_some());
+
+    let mut unmeasured = EnvLoader::new().change_file(file);
+    unmeasured.activate_streaming().unwrap();
+    assert!(unmeasured.report().elapsed.is_none());
+}
+#[test]
+fn parse_with_source_transforms_values() {
+    let file = fixture("source_transform", "SOURCE_TRANSFORM=a\u{200B}b\n");
+
+    let parsed = EnvLoader::new().change_file(file).strip_invisible_chars(true).parse_with_source().unwrap();
+
+    assert_eq!(parsed.entries[0].value, "ab");
+    assert_eq!(parsed.raw_lines()["SOURCE_TRANSFORM"], "SOURCE_TRANSFORM=a\u{200B}b");
+}